@@ -12,6 +12,9 @@
 /// if a word is already colored yellow, that word will continue to be yellow.
 pub(crate) fn wrap_ansi_escape_each_line(ansi: &ANSI, body: impl AsRef<str>) -> String {
     let ansi_escape = ansi.to_str();
+    if ansi_escape.is_empty() {
+        return body.as_ref().to_string();
+    }
     body.as_ref()
         .split('\n')
         // If sub contents are colorized it will contain SUBCOLOR ... RESET. After the reset,
@@ -26,7 +29,11 @@ pub(crate) fn wrap_ansi_escape_each_line(ansi: &ANSI, body: impl AsRef<str>) ->
         .join("\n")
 }
 
-const RESET: &str = "\x1B[0m";
+pub(crate) const RESET: &str = "\x1B[0m";
+/// Move the cursor back one column
+pub(crate) const BACKSPACE: &str = "\x08";
+/// Clear from the cursor to the end of the line
+pub(crate) const CLEAR_LINE: &str = "\x1B[K";
 const RED: &str = "\x1B[0;31m";
 const YELLOW: &str = "\x1B[0;33m";
 const BOLD_CYAN: &str = "\x1B[1;36m";
@@ -34,7 +41,7 @@ const BOLD_PURPLE: &str = "\x1B[1;35m";
 const BOLD_UNDERLINE_CYAN: &str = "\x1B[1;4;36m";
 const DIM: &str = "\x1B[2;1m"; // Default color but softer/less vibrant
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[allow(clippy::upper_case_acronyms)]
 pub(crate) enum ANSI {
     Dim,
@@ -43,10 +50,22 @@ pub(crate) enum ANSI {
     BoldCyan,
     BoldUnderlineCyan,
     BoldPurple,
+    /// A full escape sequence such as `\x1B[1;35m` or an empty string for no styling
+    Custom(String),
 }
 
 impl ANSI {
-    fn to_str(&self) -> &'static str {
+    /// Build a style from SGR parameters such as `1;35`
+    pub(crate) fn from_sgr(parameters: impl AsRef<str>) -> Self {
+        let parameters = parameters.as_ref();
+        if parameters.is_empty() {
+            ANSI::Custom(String::new())
+        } else {
+            ANSI::Custom(format!("\x1B[{parameters}m"))
+        }
+    }
+
+    pub(crate) fn to_str(&self) -> &str {
         match self {
             ANSI::Dim => DIM,
             ANSI::Red => RED,
@@ -54,39 +73,324 @@ impl ANSI {
             ANSI::BoldCyan => BOLD_CYAN,
             ANSI::BoldPurple => BOLD_PURPLE,
             ANSI::BoldUnderlineCyan => BOLD_UNDERLINE_CYAN,
+            ANSI::Custom(escape) => escape,
         }
     }
 }
 
-/// Removes ANSI escape sequences generated from this library from a string.
+/// Controls if ANSI escape sequences (colors) are emitted.
 ///
-/// Not guaranteed to remove all ANSI escape sequences, only those generated by this library.
-pub fn strip_ansi(contents: impl AsRef<str>) -> String {
-    let contents = contents.as_ref();
-    let mut result = String::with_capacity(contents.len());
-    let mut in_sequence = false;
-    for char in contents.chars() {
-        // If current character is an escape, set the escape flag which will begin ignoring characters
-        // until the end of the sequence is found.
-        if char == '\x1B' {
-            in_sequence = true;
-        } else if in_sequence {
-            // If we're in a sequence discard the character, an 'm' indicates the end of the sequence
-            if char == 'm' {
-                in_sequence = false;
+/// Use with [`crate::Print::with_color`] or [`crate::global::set_color`].
+///
+/// ```
+/// use bullet_stream::{ColorChoice, Print};
+///
+/// let output = Print::new(Vec::new())
+///     .with_color(ColorChoice::Never)
+///     .h2("No colors here")
+///     .done();
+///
+/// assert_eq!(
+///     bullet_stream::strip_ansi(String::from_utf8_lossy(&output)),
+///     String::from_utf8_lossy(&output)
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Always emit colors regardless of the environment or the writer.
+    Always,
+    /// Never emit colors. The output is the same as calling [`strip_ansi`] on colored output.
+    Never,
+    /// Decide based on the environment and the writer (default).
+    ///
+    /// In order of precedence:
+    ///
+    /// - `FORCE_COLOR` or `CLICOLOR_FORCE` set to a value other than `0` enables colors.
+    /// - `NO_COLOR` set to any non-empty value disables colors.
+    /// - `CLICOLOR=0` disables colors.
+    /// - Otherwise colors are enabled when the writer is a terminal (TTY). Only
+    ///   `std::io::Stdout`, `std::io::Stderr` (and their locks) and `std::fs::File` can be
+    ///   detected as a terminal, any other writer is treated as a non-terminal.
+    #[default]
+    Auto,
+}
+
+impl ColorChoice {
+    /// Resolve the choice into "should colors be emitted" for a writer
+    pub(crate) fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => auto_color(is_terminal, |key| std::env::var(key).ok()),
+        }
+    }
+}
+
+fn auto_color(is_terminal: bool, env: impl Fn(&str) -> Option<String>) -> bool {
+    let forced = |key| env(key).is_some_and(|value| !value.is_empty() && value != "0");
+
+    if forced("FORCE_COLOR") || forced("CLICOLOR_FORCE") {
+        true
+    } else if env("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || env("CLICOLOR").is_some_and(|value| value == "0")
+    {
+        false
+    } else {
+        is_terminal
+    }
+}
+
+/// Removes ANSI escape sequences from bytes as they're written.
+///
+/// By default produces the same output as [`strip_ansi`] without needing the full contents up
+/// front. State is preserved between calls so a sequence split across two writes is still removed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AnsiStripper {
+    sequence: Option<Vec<u8>>,
+    keep_non_color: bool,
+}
+
+impl AnsiStripper {
+    /// Only remove colors (SGR sequences), others such as erasing a line are kept
+    pub(crate) fn colors() -> Self {
+        Self {
+            sequence: None,
+            keep_non_color: true,
+        }
+    }
+
+    pub(crate) fn strip(&mut self, buf: &[u8]) -> Vec<u8> {
+        let mut result = Vec::with_capacity(buf.len());
+        for &byte in buf {
+            // Escape sequences are ASCII, they never appear inside of a multi-byte UTF-8 character
+            if byte == b'\x1B' {
+                self.sequence = Some(vec![byte]);
+            } else if let Some(sequence) = self.sequence.as_mut() {
+                sequence.push(byte);
+                // The final byte of a sequence is in the range `@` to `~`, except for the `[` that
+                // introduces it. Color (SGR) sequences end in `m`.
+                if sequence.len() > 2 && (0x40..=0x7E).contains(&byte) {
+                    if self.keep_non_color && byte != b'm' {
+                        result.append(sequence);
+                    }
+                    self.sequence = None;
+                }
+            } else {
+                result.push(byte);
+            }
+        }
+        result
+    }
+}
+
+/// Rewrites bytes as they're written so each line stands on its own.
+///
+/// Git prefixes every line of a push with `remote: `, any state carried over a newline leaks
+/// into that prefix and anything redrawn in place turns into garbage:
+///
+/// - Colors (SGR sequences) are reset before a newline and applied again before the first
+///   character of the next line that is not indentation.
+/// - A carriage return that redraws a line becomes a newline, `\r\n` becomes `\n`.
+/// - Other escape sequences and backspaces, which move the cursor or erase text, are removed.
+///
+/// State is preserved between calls so a sequence or `\r\n` split across two writes is handled.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LineSafe {
+    sequence: Option<Vec<u8>>,
+    /// SGR sequences in effect, applied again at the start of each line
+    colors: Vec<u8>,
+    /// Colors were written on the current line and must be reset before the newline
+    open: bool,
+    /// Text other than indentation was written since the last newline
+    mid_line: bool,
+    /// A carriage return was seen, it is dropped if a newline follows
+    carriage_return: bool,
+}
+
+impl LineSafe {
+    pub(crate) fn normalize(&mut self, buf: &[u8]) -> Vec<u8> {
+        let mut result = Vec::with_capacity(buf.len());
+        for &byte in buf {
+            if let Some(sequence) = self.sequence.as_mut() {
+                // Same rules for the start and end of a sequence as `AnsiStripper`
+                if byte == b'\x1B' {
+                    sequence.clear();
+                }
+                sequence.push(byte);
+                if sequence.len() > 2 && (0x40..=0x7E).contains(&byte) {
+                    let sequence = std::mem::take(sequence);
+                    self.sequence = None;
+                    if byte == b'm' && sequence[1] == b'[' {
+                        self.color(sequence, &mut result);
+                    }
+                }
+                continue;
             }
+
+            match byte {
+                b'\x1B' => self.sequence = Some(vec![byte]),
+                // Redrawing an empty line leaves nothing behind to keep
+                b'\r' => self.carriage_return = self.mid_line,
+                b'\n' => {
+                    self.carriage_return = false;
+                    self.newline(&mut result);
+                }
+                b'\x08' => {}
+                _ => {
+                    // The line is only redrawn once there is something to draw
+                    if std::mem::take(&mut self.carriage_return) {
+                        self.newline(&mut result);
+                    }
+                    if !self.mid_line {
+                        // Indentation is left uncolored
+                        if byte == b' ' || byte == b'\t' {
+                            result.push(byte);
+                            continue;
+                        }
+                        self.mid_line = true;
+                        self.open = !self.colors.is_empty();
+                        result.extend_from_slice(&self.colors);
+                    }
+                    result.push(byte);
+                }
+            }
+        }
+        result
+    }
+
+    fn color(&mut self, sequence: Vec<u8>, result: &mut Vec<u8>) {
+        let parameters = &sequence[2..sequence.len() - 1];
+        if parameters.is_empty() || parameters == b"0" {
+            self.colors.clear();
+        } else if parameters.starts_with(b"0;") {
+            self.colors.clone_from(&sequence);
         } else {
-            result.push(char);
+            self.colors.extend_from_slice(&sequence);
+        }
+
+        // At the start of a line colors are written along with the first character
+        if self.mid_line && !self.carriage_return {
+            result.extend_from_slice(&sequence);
+            self.open = !self.colors.is_empty();
+        }
+    }
+
+    fn newline(&mut self, result: &mut Vec<u8>) {
+        if self.open {
+            result.extend_from_slice(RESET.as_bytes());
+            self.open = false;
+        }
+        result.push(b'\n');
+        self.mid_line = false;
+    }
+}
+
+/// Rewrite streamed output with [`LineSafe`] and split it into lines that each keep their newline
+///
+/// Colors are closed at the end of each line, so a prefix such as the indentation of a stream
+/// can be added to every redraw without being colored.
+pub(crate) fn split_redraws(contents: &[u8]) -> Vec<Vec<u8>> {
+    LineSafe::default()
+        .normalize(contents)
+        .split_inclusive(|&byte| byte == b'\n')
+        .map(<[u8]>::to_vec)
+        .collect()
+}
+
+/// Tracks the visible column of the cursor as bytes are written.
+///
+/// Escape sequences don't take up space on screen and are not counted. State is preserved between
+/// calls so a sequence split across two writes is still ignored.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct VisibleColumn {
+    column: usize,
+    sequence: Option<usize>,
+}
+
+impl VisibleColumn {
+    pub(crate) fn update(&mut self, buf: &[u8]) {
+        for &byte in buf {
+            if byte == b'\x1B' {
+                self.sequence = Some(1);
+            } else if let Some(len) = self.sequence.as_mut() {
+                // Same rules for the end of a sequence as `AnsiStripper`
+                *len += 1;
+                if *len > 2 && (0x40..=0x7E).contains(&byte) {
+                    self.sequence = None;
+                }
+            } else {
+                match byte {
+                    b'\n' | b'\r' => self.column = 0,
+                    b'\x08' => self.column = self.column.saturating_sub(1),
+                    // Count characters, not the continuation bytes of multi-byte UTF-8 characters
+                    _ if byte >= b' ' && byte & 0xC0 != 0x80 => self.column += 1,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Number of visible characters since the start of the line
+    pub(crate) fn get(&self) -> usize {
+        self.column
+    }
+}
+
+/// Visible width of a single line of text, not counting escape sequences
+pub(crate) fn visible_width(contents: impl AsRef<str>) -> usize {
+    let mut column = VisibleColumn::default();
+    column.update(contents.as_ref().as_bytes());
+    column.get()
+}
+
+/// Byte ranges of the escape sequences in a string, an unfinished sequence runs to the end
+pub(crate) fn escape_sequences(contents: &str) -> Vec<std::ops::Range<usize>> {
+    let mut sequences = Vec::new();
+    let mut start = None;
+    for (index, &byte) in contents.as_bytes().iter().enumerate() {
+        if byte == b'\x1B' {
+            start = Some(index);
+        } else if let Some(begin) = start {
+            // Same rules for the end of a sequence as `AnsiStripper`
+            if index - begin > 1 && (0x40..=0x7E).contains(&byte) {
+                sequences.push(begin..index + 1);
+                start = None;
+            }
         }
     }
-    result.shrink_to_fit();
+    if let Some(begin) = start {
+        sequences.push(begin..contents.len());
+    }
+    sequences
+}
 
-    result
+/// Rows a line takes up on a screen `width` columns wide, a line that doesn't fit wraps
+pub(crate) fn rows(line: impl AsRef<str>, width: usize) -> usize {
+    visible_width(line.as_ref().trim_end_matches('\n'))
+        .div_ceil(width.max(1))
+        .max(1)
+}
+
+/// Removes ANSI escape sequences from a string.
+///
+/// Every sequence that starts with an escape (`\x1B`) is removed through its final byte, a
+/// character from `@` to `~`, such as the `m` of a color or the `K` of erasing a line.
+pub fn strip_ansi(contents: impl AsRef<str>) -> String {
+    String::from_utf8_lossy(&AnsiStripper::default().strip(contents.as_ref().as_bytes()))
+        .into_owned()
+}
+
+/// Like [`strip_ansi`] but keeps escape sequences that are not colors, such as erasing a line
+#[cfg(test)]
+pub(crate) fn strip_colors(contents: impl AsRef<str>) -> String {
+    String::from_utf8_lossy(&AnsiStripper::colors().strip(contents.as_ref().as_bytes()))
+        .into_owned()
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::util::fake_env;
 
     #[test]
     fn test_strip_ansi() {
@@ -97,6 +401,7 @@ mod test {
             ANSI::BoldCyan,
             ANSI::BoldPurple,
             ANSI::BoldUnderlineCyan,
+            ANSI::from_sgr("1;38;5;208"),
         ] {
             // Ensure we covered each color, if a new color is added to ANSI, but not
             // to this test, it won't compile
@@ -107,6 +412,7 @@ mod test {
                 ANSI::BoldCyan => (),
                 ANSI::BoldUnderlineCyan => (),
                 ANSI::BoldPurple => (),
+                ANSI::Custom(_) => (),
             }
 
             let input = "Hello world";
@@ -167,6 +473,149 @@ mod test {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn stripper_matches_strip_ansi_across_writes() {
+        let colored = format!(
+            "{}\n{}",
+            wrap_ansi_escape_each_line(&ANSI::Red, "hello"),
+            wrap_ansi_escape_each_line(&ANSI::BoldCyan, "wörld")
+        );
+
+        for split in 0..colored.len() {
+            let (first, second) = colored.as_bytes().split_at(split);
+            let mut stripper = AnsiStripper::default();
+            let mut actual = stripper.strip(first);
+            actual.extend(stripper.strip(second));
+
+            assert_eq!(strip_ansi(&colored).into_bytes(), actual);
+        }
+    }
+
+    #[test]
+    fn strip_colors_keeps_non_color_sequences() {
+        let colored = "\x1B[0Ksection_start:1:name\r\x1B[0K\x1B[1;36mheader\x1B[0m";
+        assert_eq!(
+            "\x1B[0Ksection_start:1:name\r\x1B[0Kheader",
+            strip_colors(colored)
+        );
+        assert_eq!("section_start:1:name\rheader", strip_ansi(colored));
+    }
+
+    #[test]
+    fn line_safe_leaves_library_output_alone() {
+        let output = format!(
+            "{}\n\n{}\n",
+            wrap_ansi_escape_each_line(&ANSI::Red, "hello\nworld"),
+            wrap_ansi_escape_each_line(
+                &ANSI::Yellow,
+                format!("a {} c", wrap_ansi_escape_each_line(&ANSI::BoldCyan, "b"))
+            )
+        );
+        let actual = LineSafe::default().normalize(output.as_bytes());
+        assert_eq!(output, String::from_utf8_lossy(&actual));
+    }
+
+    #[test]
+    fn line_safe_closes_colors_and_redraws() {
+        let input = "\x1B[31merror\nstill red\x1B[0m\n\x1B[1mbold\x1B[32m \r\n\x1B[mline\n10%\r\x1B[K50%\r\r100%\n\rab\x08c\x1B[2A\n";
+        let expected = format!(
+            "\x1B[31merror{RESET}\n\x1B[31mstill red{RESET}\n\x1B[1mbold\x1B[32m {RESET}\nline\n10%\n50%\n100%\nabc\n"
+        );
+        assert_eq!(
+            expected,
+            String::from_utf8_lossy(&LineSafe::default().normalize(input.as_bytes()))
+        );
+
+        for split in 0..input.len() {
+            let (first, second) = input.as_bytes().split_at(split);
+            let mut line_safe = LineSafe::default();
+            let mut actual = line_safe.normalize(first);
+            actual.extend(line_safe.normalize(second));
+
+            assert_eq!(expected, String::from_utf8_lossy(&actual));
+        }
+
+        let twice = LineSafe::default().normalize(expected.as_bytes());
+        assert_eq!(expected, String::from_utf8_lossy(&twice));
+    }
+
+    #[test]
+    fn split_redraws_into_lines() {
+        assert_eq!(
+            vec![
+                b"10%\n".to_vec(),
+                b"50%\n".to_vec(),
+                b"\n".to_vec(),
+                b"\x1B[31mdone\x1B[0m\n".to_vec(),
+                b"\x1B[31mtail".to_vec()
+            ],
+            split_redraws(b"\r10%\r50%\r\r\n\n\x1B[31mdone\r\ntail")
+        );
+        assert!(split_redraws(b"").is_empty());
+    }
+
+    #[test]
+    fn visible_column_ignores_escapes() {
+        let colored = format!(
+            "{}\n{}",
+            wrap_ansi_escape_each_line(&ANSI::Red, "hello"),
+            wrap_ansi_escape_each_line(&ANSI::BoldCyan, "wörld")
+        );
+        for split in 0..colored.len() {
+            let (first, second) = colored.as_bytes().split_at(split);
+            let mut column = VisibleColumn::default();
+            column.update(first);
+            column.update(second);
+
+            assert_eq!(5, column.get());
+        }
+
+        assert_eq!(3, visible_width(format!("ab{CLEAR_LINE}c")));
+        assert_eq!(1, visible_width(format!("ab{BACKSPACE}")));
+    }
+
+    #[test]
+    fn finds_escape_sequences() {
+        assert_eq!(
+            vec![1..8, 9..13, 14..16],
+            escape_sequences("a\x1B[0;33mb\x1B[0mc\x1B[")
+        );
+        assert!(escape_sequences("plain").is_empty());
+    }
+
+    #[test]
+    fn counts_wrapped_rows() {
+        assert_eq!(1, rows("\n", 10));
+        assert_eq!(1, rows("0123456789\n", 10));
+        assert_eq!(2, rows("0123456789a\n", 10));
+        assert_eq!(3, rows("\x1B[1m0123456789\x1B[0m0123456789a", 10));
+    }
+
+    #[test]
+    fn auto_color_env() {
+        assert!(auto_color(true, fake_env(&[])));
+        assert!(!auto_color(false, fake_env(&[])));
+        assert!(!auto_color(true, fake_env(&[("NO_COLOR", "1")])));
+        assert!(auto_color(true, fake_env(&[("NO_COLOR", "")])));
+        assert!(!auto_color(true, fake_env(&[("CLICOLOR", "0")])));
+        assert!(auto_color(false, fake_env(&[("FORCE_COLOR", "1")])));
+        assert!(auto_color(false, fake_env(&[("CLICOLOR_FORCE", "1")])));
+        assert!(!auto_color(false, fake_env(&[("FORCE_COLOR", "0")])));
+        assert!(auto_color(
+            false,
+            fake_env(&[("FORCE_COLOR", "1"), ("NO_COLOR", "1")])
+        ));
+    }
+
+    #[test]
+    fn custom_style() {
+        let actual = wrap_ansi_escape_each_line(&ANSI::from_sgr("1;4;31"), "hello");
+        assert_eq!(format!("\x1B[1;4;31mhello{RESET}"), actual);
+
+        let actual = wrap_ansi_escape_each_line(&ANSI::from_sgr(""), "hello");
+        assert_eq!("hello", actual);
+    }
+
     #[test]
     fn simple_case() {
         let actual = wrap_ansi_escape_each_line(&ANSI::Red, "hello world");
@@ -1,12 +1,64 @@
 //! This module is responsible for the logic involved in the printing to output while
 //! other work is being performed. Such as printing dots while a download is being performed.
+use crate::ansi_escape::{strip_ansi, visible_width, BACKSPACE, CLEAR_LINE};
 use std::io::Write;
 use std::sync::mpsc::{channel, Sender};
 use std::thread::JoinHandle;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// What a background printer writes at each interval
+pub(crate) enum Tick {
+    /// Append the same text every interval, such as a dot
+    Append(String),
+    /// Replace the previous frame with a new one, such as a spinner. Only works on terminals.
+    ///
+    /// The function receives the time elapsed since the printer started.
+    Redraw(Box<dyn FnMut(Duration) -> String + Send>),
+}
+
+impl std::fmt::Debug for Tick {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tick::Append(tick) => f.debug_tuple("Append").field(tick).finish(),
+            Tick::Redraw(_) => f.debug_tuple("Redraw").field(&"Fn()").finish(),
+        }
+    }
+}
+
+/// Continue appended ticks on a new line instead of running past the edge of the screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Wrap {
+    /// Maximum number of columns in a line
+    pub(crate) width: usize,
+    /// Column of the cursor when the printer starts
+    pub(crate) column: usize,
+    /// Written at the start of each continuation line
+    pub(crate) indent: String,
+}
+
+/// Room left on the last line for the duration written after a timer stops, such as `(12m 34s)`
+const DURATION_WIDTH: usize = " (12m 34s)".len();
+
+impl Wrap {
+    /// Returns the text to write before `text` so it fits within the width
+    fn before(&mut self, text: &str) -> String {
+        let width = visible_width(text);
+        let indent = visible_width(&self.indent);
+        let mut before = String::new();
+        if self.column + width > self.width && self.column > indent {
+            before = format!("\n{}", self.indent);
+            self.column = indent;
+        }
+        self.column += width;
+        before
+    }
+}
 
 /// Repeatedly prints `tick` to the given buffer at the given interval. The `start` argument will be printed before the first `tick` and the `end` argument will be printed after the last `tick` when the timer is stopped.
 ///
+/// When `wrap` is given, appended ticks that would go past the width continue on an indented line.
+/// Redrawn ticks are not wrapped.
+///
 /// Returns a struct that allows for manually stopping the timer or will automatically stop
 /// the timer if the guard is dropped. This functionality allows for errors that trigger
 /// an exit of the function to not accidentally have a timer printing in the background
@@ -16,9 +68,10 @@ pub(crate) fn print_interval<W>(
     mut buffer: W,
     interval: Duration,
     start: String,
-    tick: String,
+    tick: Tick,
     end: String,
     on_drop_msg: String,
+    wrap: Option<Wrap>,
 ) -> PrintGuard<W>
 where
     W: Write + Send + 'static,
@@ -26,25 +79,157 @@ where
     let (sender, receiver) = channel::<()>();
 
     let join_handle = std::thread::spawn(move || {
+        let mut ticker = Ticker::start(&mut buffer, start, tick, end, wrap);
+        loop {
+            ticker.tick(&mut buffer);
+            if receiver.recv_timeout(interval).is_ok() {
+                break;
+            }
+        }
+        ticker.stop(&mut buffer);
+
+        buffer
+    });
+
+    PrintGuard::new(join_handle, sender, on_drop_msg)
+}
+
+/// Writes the output of a background printer
+pub(crate) struct Ticker {
+    started: Instant,
+    tick: Tick,
+    end: String,
+    wrap: Option<Wrap>,
+    /// Width of the frame on screen when redrawing
+    drawn: usize,
+}
+
+impl Ticker {
+    pub(crate) fn start<W: Write>(
+        buffer: &mut W,
+        start: String,
+        tick: Tick,
+        end: String,
+        mut wrap: Option<Wrap>,
+    ) -> Self {
+        if let Some(wrap) = &mut wrap {
+            wrap.column += visible_width(&start);
+        }
         write!(buffer, "{start}").expect("Writer should not be closed");
         buffer.flush().expect("Writer should not be closed");
 
-        loop {
-            write!(buffer, "{tick}").expect("Writer should not be closed");
-            buffer.flush().expect("Writer should not be closed");
+        Self {
+            started: Instant::now(),
+            tick,
+            end,
+            wrap,
+            drawn: 0,
+        }
+    }
 
-            if receiver.recv_timeout(interval).is_ok() {
-                break;
+    pub(crate) fn tick<W: Write>(&mut self, buffer: &mut W) {
+        match &mut self.tick {
+            Tick::Append(tick) => {
+                let before = self
+                    .wrap
+                    .as_mut()
+                    .map(|wrap| wrap.before(tick))
+                    .unwrap_or_default();
+                write!(buffer, "{before}{tick}")
+            }
+            Tick::Redraw(frame) => {
+                let frame = frame(self.started.elapsed());
+                let erase = BACKSPACE.repeat(self.drawn);
+                self.drawn = strip_ansi(&frame).chars().count();
+                write!(buffer, "{erase}{frame}{CLEAR_LINE}")
             }
         }
+        .expect("Writer should not be closed");
+        buffer.flush().expect("Writer should not be closed");
+    }
 
-        write!(buffer, "{end}").expect("Writer should not be closed");
+    pub(crate) fn stop<W: Write>(mut self, buffer: &mut W) {
+        if self.drawn > 0 {
+            write!(buffer, "{}{CLEAR_LINE}", BACKSPACE.repeat(self.drawn))
+                .expect("Writer should not be closed");
+        }
+        // The end and the duration after it count toward the width
+        let before = self
+            .wrap
+            .as_mut()
+            .map(|wrap| wrap.before(&format!("{}{}", self.end, " ".repeat(DURATION_WIDTH))))
+            .unwrap_or_default();
+        write!(buffer, "{before}{}", self.end).expect("Writer should not be closed");
         buffer.flush().expect("Writer should not be closed");
+    }
+}
+
+/// Same as [`print_interval`] but ticks are driven by a `tokio` task instead of a thread.
+///
+/// Must be called from within a `tokio` runtime. When the guard is dropped without being stopped
+/// the task writes `on_drop_msg` and exits on its own, as there's no way to wait for it in `Drop`.
+#[cfg(feature = "tokio")]
+#[must_use]
+pub(crate) fn print_interval_async<W>(
+    mut buffer: W,
+    interval: Duration,
+    start: String,
+    tick: Tick,
+    end: String,
+    on_drop_msg: String,
+    wrap: Option<Wrap>,
+) -> AsyncPrintGuard<W>
+where
+    W: Write + Send + 'static,
+{
+    let (sender, mut receiver) = tokio::sync::oneshot::channel::<()>();
+
+    let join_handle = tokio::spawn(async move {
+        let mut ticker = Ticker::start(&mut buffer, start, tick, end, wrap);
+        // Like the thread printer, always write the first tick before checking for a stop
+        ticker.tick(&mut buffer);
+        let mut interval =
+            tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let stopped = loop {
+            tokio::select! {
+                _ = interval.tick() => ticker.tick(&mut buffer),
+                result = &mut receiver => break result.is_ok(),
+            }
+        };
+        ticker.stop(&mut buffer);
+        if !stopped {
+            writeln!(buffer, "{on_drop_msg}").expect("Writer should not be closed");
+        }
 
         buffer
     });
 
-    PrintGuard::new(join_handle, sender, on_drop_msg)
+    AsyncPrintGuard {
+        stop_signal: sender,
+        join_handle,
+    }
+}
+
+/// Holds the reference to an async background printer.
+///
+/// Dropping the guard signals the task to stop, there's no need to implement `Drop`.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub(crate) struct AsyncPrintGuard<W> {
+    stop_signal: tokio::sync::oneshot::Sender<()>,
+    join_handle: tokio::task::JoinHandle<W>,
+}
+
+#[cfg(feature = "tokio")]
+impl<W> AsyncPrintGuard<W> {
+    /// Stop the background printer and receive the original buffer.
+    pub(crate) async fn stop(self) -> Result<W, tokio::task::JoinError> {
+        // Ignore if the channel is closed, likely means the task died which
+        // we want in this case.
+        let _ = self.stop_signal.send(());
+        self.join_handle.await
+    }
 }
 
 /// Holds the reference to the background printer.
@@ -168,9 +353,10 @@ mod test {
             buffer,
             Duration::from_millis(1),
             String::from(" ."),
-            String::from("."),
+            Tick::Append(String::from(".")),
             String::from(". "),
             String::from("(Error)"),
+            None,
         );
         let mut writer = dot.stop().unwrap();
 
@@ -180,6 +366,72 @@ mod test {
         assert_eq!("before ... after", String::from_utf8_lossy(&writer));
     }
 
+    #[test]
+    fn wraps_appended_ticks() {
+        let mut buffer: Vec<u8> = vec![];
+        write!(buffer, "  - Hi").unwrap();
+
+        let dot = print_interval(
+            buffer,
+            Duration::from_millis(1),
+            String::from(" ."),
+            Tick::Append(String::from(".")),
+            String::from(". "),
+            String::from("(Error)"),
+            Some(Wrap {
+                width: 10,
+                column: 6,
+                indent: String::from("    "),
+            }),
+        );
+        std::thread::sleep(Duration::from_millis(50));
+        let writer = dot.stop().unwrap();
+        let output = String::from_utf8_lossy(&writer);
+        let lines = output.lines().collect::<Vec<_>>();
+
+        assert!(lines.len() > 1, "{output:?}");
+        assert_eq!("  - Hi ...", lines[0]);
+        assert_eq!("    ......", lines[1]);
+        for line in &lines[1..lines.len() - 1] {
+            assert!(line.starts_with("    .") && line.len() <= 10, "{output:?}");
+        }
+        // Too narrow to fit the duration after the end on any line with ticks
+        assert_eq!("    . ", lines[lines.len() - 1], "{output:?}");
+    }
+
+    #[test]
+    fn wrap_keeps_one_tick_per_line_when_too_narrow() {
+        let mut wrap = Wrap {
+            width: 5,
+            column: 2,
+            indent: String::from("    "),
+        };
+        assert_eq!("", wrap.before("..."));
+        assert_eq!("\n    ", wrap.before("..."));
+        assert_eq!("\n    ", wrap.before("..."));
+        assert_eq!(7, wrap.column);
+    }
+
+    #[test]
+    fn redraw_erases_frames() {
+        let mut frames = ["|", "/"].into_iter().cycle();
+        let dot = print_interval(
+            Vec::new(),
+            Duration::from_millis(50),
+            String::from(" ."),
+            Tick::Redraw(Box::new(move |_| frames.next().unwrap().to_string())),
+            String::from(". "),
+            String::from("(Error)"),
+            None,
+        );
+        std::thread::sleep(Duration::from_millis(75));
+        let writer = dot.stop().unwrap();
+        let output = String::from_utf8_lossy(&writer);
+
+        assert!(output.starts_with(" .|\x1B[K\x08/\x1B[K"), "{output:?}");
+        assert!(output.ends_with("\x1B[K\x08\x1B[K. "), "{output:?}");
+    }
+
     #[test]
     fn test_drop_stops_timer() {
         let tempfile = NamedTempFile::new().unwrap();
@@ -190,9 +442,10 @@ mod test {
             log,
             Duration::from_millis(1),
             String::from(" ."),
-            String::from("."),
+            Tick::Append(String::from(".")),
             String::from(". "),
             "(Error)".to_string(),
+            None,
         );
         drop(dot);
 
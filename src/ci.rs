@@ -0,0 +1,337 @@
+//! Collapsible log groups and annotations for CI providers
+//!
+//! Long streamed sections make CI logs hard to read. When enabled with [`crate::Print::with_ci`]
+//! or [`crate::global::set_ci`], each bullet is wrapped in a collapsible group and warnings and
+//! errors become annotations where the provider supports them.
+//!
+//! ```
+//! use bullet_stream::{ci::Ci, Print};
+//!
+//! let output = Print::new(Vec::new())
+//!     .with_ci(Ci::GitHubActions)
+//!     .without_header()
+//!     .bullet("Ruby version")
+//!     .sub_bullet("Using 3.4.1")
+//!     .done()
+//!     .warning("Ruby 3.4.1 is deprecated")
+//!     .done();
+//!
+//! assert_eq!(
+//!     "::group::- Ruby version\n  - Using 3.4.1\n::endgroup::\n::warning::Ruby 3.4.1 is deprecated\n- Done (finished in < 0.1s)\n",
+//!     String::from_utf8_lossy(&output)
+//! );
+//! ```
+//!
+//! Use [`Ci::from_env`] to only enable it when running in CI:
+//!
+//! ```
+//! use bullet_stream::{ci::Ci, Print};
+//!
+//! let output = Print::new(std::io::stderr())
+//!     .with_ci(Ci::from_env())
+//!     .h2("Example Buildpack");
+//! ```
+use crate::ansi_escape::strip_ansi;
+use crate::event::Event;
+use crate::render::{Output, Renderer, TimerTicks};
+use crate::style::Theme;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A CI provider with support for collapsible log groups
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ci {
+    /// Groups use `::group::` and `::endgroup::`, warnings and errors become
+    /// `::warning::` and `::error::` annotations.
+    ///
+    /// GitHub Actions does not support nested groups.
+    GitHubActions,
+    /// Groups use `section_start` and `section_end`. GitLab does not support annotations so
+    /// warnings and errors are written as usual.
+    GitLab,
+}
+
+impl Ci {
+    /// Detect the CI provider from `GITHUB_ACTIONS` or `GITLAB_CI` environment variables
+    #[must_use]
+    pub fn from_env() -> Option<Ci> {
+        detect(|key| std::env::var(key).ok())
+    }
+}
+
+fn detect(env: impl Fn(&str) -> Option<String>) -> Option<Ci> {
+    if env("GITHUB_ACTIONS").is_some_and(|value| value == "true") {
+        Some(Ci::GitHubActions)
+    } else if env("GITLAB_CI").is_some_and(|value| value == "true") {
+        Some(Ci::GitLab)
+    } else {
+        None
+    }
+}
+
+/// Wraps another [`Renderer`] to add CI groups and annotations.
+///
+/// By default each bullet becomes a group. With [`CiRenderer::with_stream_groups`] each stream
+/// also becomes a group. On GitHub Actions, which doesn't support nesting, streams are grouped
+/// instead of bullets.
+#[derive(Debug)]
+pub struct CiRenderer {
+    ci: Ci,
+    stream_groups: bool,
+    inner: Arc<dyn Renderer>,
+    state: Mutex<GroupState>,
+}
+
+#[derive(Debug, Default)]
+struct GroupState {
+    bullet: Option<String>,
+    stream: Option<String>,
+    count: usize,
+}
+
+impl CiRenderer {
+    pub fn new(ci: Ci, inner: Arc<dyn Renderer>) -> Self {
+        Self {
+            ci,
+            stream_groups: false,
+            inner,
+            state: Mutex::new(GroupState::default()),
+        }
+    }
+
+    /// Also group each stream such as from [`crate::Print::stream_cmd`]
+    #[must_use]
+    pub fn with_stream_groups(mut self, enabled: bool) -> Self {
+        self.stream_groups = enabled;
+        self
+    }
+
+    fn group_bullets(&self) -> bool {
+        !(self.stream_groups && self.ci == Ci::GitHubActions)
+    }
+
+    fn start(&self, out: &mut Output<'_>, state: &mut GroupState) -> String {
+        state.count += 1;
+        let name = format!("bullet_stream_{}", state.count);
+        match self.ci {
+            Ci::GitHubActions => write!(out, "::group::"),
+            Ci::GitLab => write!(
+                out,
+                "\x1B[0Ksection_start:{}:{name}\r\x1B[0K",
+                unix_seconds()
+            ),
+        }
+        .expect("writer open");
+        name
+    }
+
+    fn end(&self, out: &mut Output<'_>, name: Option<String>) {
+        if let Some(name) = name {
+            match self.ci {
+                Ci::GitHubActions => writeln!(out, "::endgroup::"),
+                Ci::GitLab => {
+                    writeln!(out, "\x1B[0Ksection_end:{}:{name}\r\x1B[0K", unix_seconds())
+                }
+            }
+            .expect("writer open");
+        }
+    }
+
+    fn annotate(out: &mut Output<'_>, kind: &str, contents: &str) {
+        let message = strip_ansi(contents.trim())
+            .replace('%', "%25")
+            .replace('\r', "%0D")
+            .replace('\n', "%0A");
+        writeln!(out, "::{kind}::{message}").expect("writer open");
+    }
+}
+
+impl Renderer for CiRenderer {
+    fn render(&self, out: &mut Output<'_>, event: &Event) {
+        let mut state = self
+            .state
+            .lock()
+            .expect("CI renderer lock poisoned - cannot guarantee data consistency");
+
+        match event {
+            Event::H1(_)
+            | Event::H2(_)
+            | Event::H3(_)
+            | Event::Bullet(_)
+            | Event::BulletDone
+            | Event::AllDone(_) => {
+                let stream = state.stream.take();
+                self.end(out, stream);
+                let bullet = state.bullet.take();
+                self.end(out, bullet);
+            }
+            _ => {}
+        }
+
+        match (self.ci, event) {
+            (Ci::GitHubActions, Event::Warning(s)) => Self::annotate(out, "warning", s),
+            (Ci::GitHubActions, Event::Error(s)) => Self::annotate(out, "error", s),
+            (_, Event::Bullet(_)) if self.group_bullets() => {
+                state.bullet = Some(self.start(out, &mut state));
+                self.inner.render(out, event);
+            }
+            (_, Event::StreamStart(_)) if self.stream_groups => {
+                state.stream = Some(self.start(out, &mut state));
+                self.inner.render(out, event);
+            }
+            _ => self.inner.render(out, event),
+        }
+
+        // The stream's result belongs inside of its group
+        if let Event::StreamDone(_) | Event::StreamsDone { .. } = event {
+            let stream = state.stream.take();
+            self.end(out, stream);
+        }
+    }
+
+    fn stream_line(&self, line: Vec<u8>) -> Vec<u8> {
+        self.inner.stream_line(line)
+    }
+
+    fn timer_ticks(&self, theme: &Theme) -> TimerTicks {
+        self.inner.timer_ticks(theme)
+    }
+}
+
+fn unix_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::fake_env;
+    use crate::{ColorChoice, Print};
+    use indoc::formatdoc;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn detect_from_env() {
+        assert_eq!(None, detect(fake_env(&[])));
+        assert_eq!(None, detect(fake_env(&[("GITHUB_ACTIONS", "false")])));
+        assert_eq!(
+            Some(Ci::GitHubActions),
+            detect(fake_env(&[("GITHUB_ACTIONS", "true")]))
+        );
+        assert_eq!(Some(Ci::GitLab), detect(fake_env(&[("GITLAB_CI", "true")])));
+    }
+
+    #[test]
+    fn github_stream_groups() {
+        let mut stream = Print::new(Vec::new())
+            .with_color(ColorChoice::Never)
+            .with_renderer(
+                CiRenderer::new(Ci::GitHubActions, Arc::new(crate::render::TextRenderer))
+                    .with_stream_groups(true),
+            )
+            .without_header()
+            .bullet("Bullet")
+            .start_stream("Streaming");
+        writeln!(stream, "hello").unwrap();
+        let output = stream
+            .done()
+            .done()
+            .bullet("Next")
+            .done()
+            .error("Multi\nline");
+
+        assert_eq!(
+            formatdoc! {"
+                - Bullet
+                ::group::  - Streaming
+
+                      hello
+
+                  - Done (< 0.1s)
+                ::endgroup::
+                - Next
+                ::error::Multi%0Aline
+            "},
+            String::from_utf8_lossy(&output)
+        );
+    }
+
+    #[test]
+    fn ci_is_replaced_instead_of_wrapped_again() {
+        let output = Print::new(Vec::new())
+            .with_color(ColorChoice::Never)
+            .with_ci(Ci::GitLab)
+            .with_ci(Ci::GitHubActions)
+            .with_renderer(crate::render::TextRenderer)
+            .without_header()
+            .bullet("Bullet")
+            .done()
+            .done();
+
+        assert_eq!(
+            formatdoc! {"
+                ::group::- Bullet
+                ::endgroup::
+                - Done (finished in < 0.1s)
+            "},
+            String::from_utf8_lossy(&output)
+        );
+    }
+
+    #[test]
+    fn none_stops_grouping() {
+        let output = Print::new(Vec::new())
+            .with_color(ColorChoice::Never)
+            .with_ci(Ci::GitHubActions)
+            .with_ci(None)
+            .without_header()
+            .bullet("Bullet")
+            .done()
+            .done();
+        assert_eq!(
+            "- Bullet\n- Done (finished in < 0.1s)\n",
+            String::from_utf8_lossy(&output)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "global_functions")]
+    fn global_none_stops_grouping() {
+        let output = crate::global::with_locked_writer(Vec::<u8>::new(), || {
+            crate::global::set_color(ColorChoice::Never);
+            crate::global::set_ci(Ci::GitHubActions);
+            crate::global::set_ci(None);
+            crate::global::print::bullet("Bullet");
+        });
+        assert_eq!("- Bullet\n", String::from_utf8_lossy(&output));
+    }
+
+    #[test]
+    fn gitlab_sections() {
+        let output = Print::new(Vec::new())
+            .with_color(ColorChoice::Never)
+            .with_ci(Ci::GitLab)
+            .without_header()
+            .bullet("Bullet")
+            .sub_bullet("Sub")
+            .done()
+            .warning("Careful")
+            .done();
+
+        let output = String::from_utf8_lossy(&output);
+        let lines = output.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("\x1B[0Ksection_start:"), "{output}");
+        assert!(
+            lines[0].ends_with(":bullet_stream_1\r\x1B[0K- Bullet"),
+            "{output}"
+        );
+        assert_eq!("  - Sub", lines[1]);
+        assert!(lines[2].starts_with("\x1B[0Ksection_end:"), "{output}");
+        assert!(lines[2].ends_with(":bullet_stream_1\r\x1B[0K"), "{output}");
+        assert_eq!(["", "! Careful", ""], lines[3..6]);
+    }
+}
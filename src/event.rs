@@ -0,0 +1,341 @@
+//! Structured events that parallel the human readable output
+//!
+//! Every call that prints a header, bullet, paragraph, stream, or timer also emits an [`Event`]
+//! to any registered [`EventSink`]. This allows building machine readable logs without parsing
+//! the rendered text.
+//!
+//! Register a sink with [`crate::Print::with_events`] or [`crate::global::add_event_sink`].
+//! The [`JsonLines`] sink writes one JSON object per line:
+//!
+//! ```
+//! use bullet_stream::{event::JsonLines, Print};
+//!
+//! let file = tempfile::NamedTempFile::new().unwrap();
+//! Print::new(std::io::sink())
+//!     .with_events(JsonLines::new(file.reopen().unwrap()))
+//!     .h2("Example Buildpack")
+//!     .bullet("Ruby version")
+//!     .sub_bullet("Using 3.4.1")
+//!     .done()
+//!     .done();
+//!
+//! let json = std::fs::read_to_string(file.path()).unwrap();
+//! let lines = json.lines().collect::<Vec<_>>();
+//! assert_eq!(5, lines.len());
+//! assert!(lines[0].contains(r#""type":"h2","depth":0,"text":"Example Buildpack""#));
+//! assert!(lines[2].contains(r#""type":"sub_bullet","depth":2,"text":"Using 3.4.1""#));
+//! ```
+use crate::ansi_escape::strip_ansi;
+use std::fmt::Debug;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A single piece of output.
+///
+/// Text is provided as it was passed in, including any ANSI escape codes added via the
+/// [`crate::style`] helpers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Event {
+    H1(String),
+    H2(String),
+    H3(String),
+    Plain(String),
+    Bullet(String),
+    /// A bullet and everything under it finished
+    BulletDone,
+    SubBullet(String),
+    /// A bullet under a sub bullet, `depth` starts at [`Event::NESTED_BULLET_DEPTH`]
+    NestedBullet {
+        depth: usize,
+        text: String,
+    },
+    Warning(String),
+    Error(String),
+    Important(String),
+    /// Announces streamed output such as from a command
+    StreamStart(String),
+    /// A single line of streamed output, including the trailing newline when present
+    StreamOutput(String),
+    /// Streamed output finished
+    StreamDone(Duration),
+    /// Several streams finished, `summary` describes how each of them went
+    StreamsDone {
+        duration: Duration,
+        summary: String,
+    },
+    /// A timer started printing dots in the background
+    TimerStart(String),
+    /// A timer finished successfully
+    TimerDone(Duration),
+    /// A timer was stopped with a reason
+    TimerCancel(String),
+    /// All output finished, with the total duration when known
+    AllDone(Option<Duration>),
+}
+
+impl Event {
+    /// The `depth` of an [`Event::NestedBullet`] directly under a sub bullet, each level
+    /// nested deeper adds one
+    pub const NESTED_BULLET_DEPTH: usize = 3;
+
+    /// The `depth` of anything directly under a bullet, one less than a nested bullet
+    pub(crate) const SUB_BULLET_DEPTH: usize = Self::NESTED_BULLET_DEPTH - 1;
+
+    /// How deeply nested the output is: headers and paragraphs are `0`, bullets `1`,
+    /// anything under a bullet (sub bullets, streams, and timers) `2`, and streamed output `3`.
+    /// Nested bullets are [`Event::NESTED_BULLET_DEPTH`] or deeper.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        match self {
+            Event::H1(_)
+            | Event::H2(_)
+            | Event::H3(_)
+            | Event::Plain(_)
+            | Event::Warning(_)
+            | Event::Error(_)
+            | Event::Important(_) => 0,
+            Event::Bullet(_) | Event::BulletDone | Event::AllDone(_) => 1,
+            Event::SubBullet(_)
+            | Event::StreamStart(_)
+            | Event::StreamDone(_)
+            | Event::StreamsDone { .. }
+            | Event::TimerStart(_)
+            | Event::TimerDone(_)
+            | Event::TimerCancel(_) => 2,
+            Event::StreamOutput(_) => 3,
+            Event::NestedBullet { depth, .. } => *depth,
+        }
+    }
+
+    /// A stable `snake_case` name for the event
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::H1(_) => "h1",
+            Event::H2(_) => "h2",
+            Event::H3(_) => "h3",
+            Event::Plain(_) => "plain",
+            Event::Bullet(_) => "bullet",
+            Event::BulletDone => "bullet_done",
+            Event::SubBullet(_) => "sub_bullet",
+            Event::NestedBullet { .. } => "nested_bullet",
+            Event::Warning(_) => "warning",
+            Event::Error(_) => "error",
+            Event::Important(_) => "important",
+            Event::StreamStart(_) => "stream_start",
+            Event::StreamOutput(_) => "stream_output",
+            Event::StreamDone(_) => "stream_done",
+            Event::StreamsDone { .. } => "streams_done",
+            Event::TimerStart(_) => "timer_start",
+            Event::TimerDone(_) => "timer_done",
+            Event::TimerCancel(_) => "timer_cancel",
+            Event::AllDone(_) => "all_done",
+        }
+    }
+
+    /// The text of the event, if any
+    #[must_use]
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            Event::H1(text)
+            | Event::H2(text)
+            | Event::H3(text)
+            | Event::Plain(text)
+            | Event::Bullet(text)
+            | Event::SubBullet(text)
+            | Event::NestedBullet { text, .. }
+            | Event::Warning(text)
+            | Event::Error(text)
+            | Event::Important(text)
+            | Event::StreamStart(text)
+            | Event::StreamOutput(text)
+            | Event::TimerStart(text)
+            | Event::TimerCancel(text)
+            | Event::StreamsDone { summary: text, .. } => Some(text),
+            Event::BulletDone | Event::StreamDone(_) | Event::TimerDone(_) | Event::AllDone(_) => {
+                None
+            }
+        }
+    }
+
+    /// The duration of the event, if any
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            Event::StreamDone(duration)
+            | Event::StreamsDone { duration, .. }
+            | Event::TimerDone(duration) => Some(*duration),
+            Event::AllDone(duration) => *duration,
+            _ => None,
+        }
+    }
+
+    /// Change the text of the event, if any
+    pub(crate) fn map_text(self, f: impl FnOnce(String) -> String) -> Self {
+        match self {
+            Event::H1(text) => Event::H1(f(text)),
+            Event::H2(text) => Event::H2(f(text)),
+            Event::H3(text) => Event::H3(f(text)),
+            Event::Plain(text) => Event::Plain(f(text)),
+            Event::Bullet(text) => Event::Bullet(f(text)),
+            Event::SubBullet(text) => Event::SubBullet(f(text)),
+            Event::NestedBullet { depth, text } => Event::NestedBullet {
+                depth,
+                text: f(text),
+            },
+            Event::Warning(text) => Event::Warning(f(text)),
+            Event::Error(text) => Event::Error(f(text)),
+            Event::Important(text) => Event::Important(f(text)),
+            Event::StreamStart(text) => Event::StreamStart(f(text)),
+            Event::StreamOutput(text) => Event::StreamOutput(f(text)),
+            Event::StreamsDone { duration, summary } => Event::StreamsDone {
+                duration,
+                summary: f(summary),
+            },
+            Event::TimerStart(text) => Event::TimerStart(f(text)),
+            Event::TimerCancel(text) => Event::TimerCancel(f(text)),
+            event @ (Event::BulletDone
+            | Event::StreamDone(_)
+            | Event::TimerDone(_)
+            | Event::AllDone(_)) => event,
+        }
+    }
+}
+
+/// Receives events as output is written.
+///
+/// Implement this trait to consume output in a structured way. See [`JsonLines`] for an example.
+pub trait EventSink: Send {
+    /// Called once per event, `at` is when the event was emitted
+    fn event(&mut self, at: SystemTime, event: &Event);
+}
+
+/// Writes each event as a JSON object on its own line ([JSON Lines](https://jsonlines.org/)).
+///
+/// Each object contains:
+///
+/// - `timestamp_ms`: Milliseconds since the Unix epoch
+/// - `type`: The [`Event::name`]
+/// - `depth`: The [`Event::depth`]
+/// - `text`: The [`Event::text`] with ANSI escape codes removed (when present)
+/// - `duration_ms`: The [`Event::duration`] in milliseconds (when present)
+#[derive(Debug)]
+pub struct JsonLines<W> {
+    write: W,
+}
+
+impl<W: Write> JsonLines<W> {
+    pub fn new(write: W) -> Self {
+        Self { write }
+    }
+
+    /// Return the original writer
+    pub fn into_inner(self) -> W {
+        self.write
+    }
+}
+
+impl<W: Write + Send> EventSink for JsonLines<W> {
+    fn event(&mut self, at: SystemTime, event: &Event) {
+        let mut line = format!(
+            r#"{{"timestamp_ms":{},"type":"{}","depth":{}"#,
+            at.duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis(),
+            event.name(),
+            event.depth()
+        );
+        if let Some(text) = event.text() {
+            line.push_str(&format!(r#","text":{}"#, json_string(&strip_ansi(text))));
+        }
+        if let Some(duration) = event.duration() {
+            line.push_str(&format!(r#","duration_ms":{}"#, duration.as_millis()));
+        }
+        line.push('}');
+
+        writeln!(self.write, "{line}").expect("Event writer not to be closed");
+        self.write.flush().expect("Event writer not to be closed");
+    }
+}
+
+/// Quote and escape a string for JSON
+pub(crate) fn json_string(contents: &str) -> String {
+    let mut result = String::with_capacity(contents.len() + 2);
+    result.push('"');
+    for c in contents.chars() {
+        match c {
+            '"' => result.push_str(r#"\""#),
+            '\\' => result.push_str(r"\\"),
+            '\n' => result.push_str(r"\n"),
+            '\r' => result.push_str(r"\r"),
+            '\t' => result.push_str(r"\t"),
+            c if u32::from(c) < 0x20 => result.push_str(&format!(r"\u{:04x}", u32::from(c))),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Registered sinks, cheap to clone so they can follow output between states
+#[derive(Clone, Default)]
+pub(crate) struct EventSinks(Vec<Arc<Mutex<dyn EventSink>>>);
+
+impl EventSinks {
+    pub(crate) fn push(&mut self, sink: impl EventSink + 'static) {
+        self.0.push(Arc::new(Mutex::new(sink)));
+    }
+
+    pub(crate) fn emit(&self, event: Event) {
+        let at = SystemTime::now();
+        for sink in &self.0 {
+            sink.lock()
+                .expect("Event sink lock poisoned - cannot guarantee data consistency")
+                .event(at, &event);
+        }
+    }
+}
+
+impl Debug for EventSinks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("EventSinks").field(&self.0.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn json_escaping() {
+        assert_eq!(r#""hello""#, json_string("hello"));
+        assert_eq!(
+            r#""quote \" slash \\ newline \n tab \t bell \u0007""#,
+            json_string("quote \" slash \\ newline \n tab \t bell \u{7}")
+        );
+        assert_eq!(r#""wörld""#, json_string("wörld"));
+    }
+
+    #[test]
+    fn json_lines_format() {
+        let mut sink = JsonLines::new(Vec::new());
+        let at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        sink.event(at, &Event::Warning("\x1B[0;33mCareful\x1B[0m".to_string()));
+        sink.event(at, &Event::TimerDone(Duration::from_millis(1500)));
+        sink.event(at, &Event::AllDone(None));
+
+        assert_eq!(
+            [
+                r#"{"timestamp_ms":1700000000123,"type":"warning","depth":0,"text":"Careful"}"#,
+                r#"{"timestamp_ms":1700000000123,"type":"timer_done","depth":2,"duration_ms":1500}"#,
+                r#"{"timestamp_ms":1700000000123,"type":"all_done","depth":1}"#,
+                ""
+            ]
+            .join("\n"),
+            String::from_utf8(sink.into_inner()).unwrap()
+        );
+    }
+}
@@ -1,17 +1,44 @@
-use crate::util::ParagraphInspectWrite;
+use crate::ansi_escape::LineSafe;
+use crate::ci::Ci;
+use crate::event::EventSink;
+use crate::redact::Redactions;
+use crate::render::Renderer;
+use crate::style::Theme;
 use crate::util::TrailingParagraph;
-use crate::util::TrailingParagraphSend;
+use crate::util::{Config, ParagraphInspectWrite};
+use crate::{ColorChoice, Redraws, Verbosity, Wrapping};
 use std::any::Any;
 use std::cell::Cell;
 use std::io::Write;
 use std::panic::catch_unwind;
 use std::panic::resume_unwind;
 use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
 use std::sync::LazyLock;
 use std::sync::Mutex;
+use std::sync::RwLock;
 
-static WRITER: LazyLock<Mutex<Box<dyn TrailingParagraphSend>>> =
-    LazyLock::new(|| Mutex::new(Box::new(ParagraphInspectWrite::new(std::io::stderr()))));
+trait WriteAny: Write + Any + Send {}
+impl<T> WriteAny for T where T: Write + Any + Send {}
+
+static WRITER: LazyLock<Mutex<ParagraphInspectWrite<Box<dyn WriteAny>>>> =
+    LazyLock::new(|| Mutex::new(boxed_writer(std::io::stderr())));
+
+/// A copy of the theme of [WRITER], the [crate::style] helpers read it without waiting on output
+static THEME: LazyLock<RwLock<Arc<Theme>>> = LazyLock::new(RwLock::default);
+
+/// Box the writer while preserving information that can only be detected from the original type
+fn boxed_writer<W: WriteAny>(new_writer: W) -> ParagraphInspectWrite<Box<dyn WriteAny>> {
+    let is_terminal = crate::util::is_terminal(&new_writer);
+    let colors_by_writer = crate::util::colors_by_writer(&new_writer);
+    let mut write = ParagraphInspectWrite::with_terminal(
+        Box::new(new_writer) as Box<dyn WriteAny>,
+        is_terminal,
+    );
+    write.colors_by_writer = colors_by_writer;
+    write.set_color(ColorChoice::Auto);
+    write
+}
 
 /// A marker struct for writing to a global writer
 ///
@@ -44,6 +71,28 @@ impl Write for GlobalWriter {
     }
 }
 
+impl GlobalWriter {
+    /// Wraps the global writer for use with functions that require a `ParagraphInspectWrite`
+    ///
+    /// Output configuration such as colors is already applied by the writer stored in the
+    /// global, so this wrapper passes bytes through unmodified. Line safety is the exception, it
+    /// is kept so streams know to split redrawn lines.
+    pub(crate) fn inspect_write() -> ParagraphInspectWrite<GlobalWriter> {
+        let w = WRITER.lock().unwrap();
+        ParagraphInspectWrite {
+            inner: GlobalWriter,
+            was_paragraph: w.trailing_paragraph(),
+            newlines_since_last_char: w.trailing_newline_count(),
+            // Output that is already line safe is unchanged by the global writer
+            config: w.config.clone(),
+            strip_ansi: None,
+            colors_by_writer: false,
+            line_safe: LineSafe::default(),
+            column: w.column.clone(),
+        }
+    }
+}
+
 impl TrailingParagraph for GlobalWriter {
     fn trailing_paragraph(&self) -> bool {
         let w = WRITER.lock().unwrap();
@@ -54,6 +103,14 @@ impl TrailingParagraph for GlobalWriter {
         let w = WRITER.lock().unwrap();
         w.trailing_newline_count()
     }
+
+    fn column(&self) -> usize {
+        WRITER.lock().unwrap().column.get()
+    }
+
+    fn config(&self) -> Config {
+        WRITER.lock().unwrap().config.clone()
+    }
 }
 
 /// Set the global writer
@@ -81,7 +138,252 @@ where
     let mut writer = WRITER
         .lock()
         .expect("Global writer lock poisoned - cannot guarantee data consistency");
-    *writer = Box::new(ParagraphInspectWrite::new(new_writer));
+    let mut config = writer.config.clone();
+    *writer = boxed_writer(new_writer);
+    config.is_terminal = writer.config.is_terminal;
+    writer.config = config;
+}
+
+/// Set when colors are emitted by the global writer
+///
+/// Calling [set_writer] resets the choice to [ColorChoice::Auto] for the new writer.
+///
+/// ```
+/// use bullet_stream::{global::{self, print}, ColorChoice};
+///
+/// let output = global::with_locked_writer(Vec::<u8>::new(), || {
+///     global::set_color(ColorChoice::Always);
+///     print::warning("Colorful");
+/// });
+/// assert_eq!("\n\u{1b}[0;33m! Colorful\u{1b}[0m\n\n", String::from_utf8_lossy(&output));
+/// ```
+pub fn set_color(choice: ColorChoice) {
+    WRITER
+        .lock()
+        .expect("Global writer lock poisoned - cannot guarantee data consistency")
+        .set_color(choice);
+}
+
+/// Set when bullets and paragraphs written by the global writer are wrapped to a width
+///
+/// Calling [set_writer] preserves the width.
+///
+/// ```
+/// use bullet_stream::{global::{self, print}, Wrapping};
+///
+/// let output = global::with_locked_writer(Vec::<u8>::new(), || {
+///     global::set_wrap(Wrapping::Columns(12));
+///     print::bullet("Installing dependencies");
+/// });
+/// assert_eq!("- Installing\n  dependencies\n", bullet_stream::strip_ansi(String::from_utf8_lossy(&output)));
+/// ```
+pub fn set_wrap(wrapping: Wrapping) {
+    let mut writer = WRITER
+        .lock()
+        .expect("Global writer lock poisoned - cannot guarantee data consistency");
+    writer.config.width = wrapping.width(writer.config.is_terminal);
+}
+
+/// Hide secrets from everything written by the global writer
+///
+/// See the [crate::redact] module for details. Calling [set_writer] preserves the redactions.
+///
+/// ```
+/// use bullet_stream::{global::{self, print}, redact::Redactions};
+///
+/// let output = global::with_locked_writer(Vec::<u8>::new(), || {
+///     global::set_redactions(Redactions::new().secret("hunter2"));
+///     print::bullet("Password hunter2");
+/// });
+/// assert_eq!("- Password [REDACTED]\n", String::from_utf8_lossy(&output));
+/// ```
+pub fn set_redactions(redactions: Redactions) {
+    WRITER
+        .lock()
+        .expect("Global writer lock poisoned - cannot guarantee data consistency")
+        .config
+        .redactions = Arc::new(redactions);
+}
+
+/// Make every line written by the global writer stand on its own
+///
+/// See [crate::Print::with_line_safe] for details. Calling [set_writer] preserves the setting.
+///
+/// ```
+/// use bullet_stream::global::{self, print};
+/// use std::io::Write;
+///
+/// let output = global::with_locked_writer(Vec::<u8>::new(), || {
+///     global::set_line_safe(true);
+///     print::sub_stream_with("Downloading", |mut stdout, _| {
+///         write!(stdout, "10%\r50%\r100%\n")
+///     })
+///     .unwrap();
+/// });
+/// assert!(bullet_stream::strip_ansi(String::from_utf8_lossy(&output))
+///     .contains("      10%\n      50%\n      100%\n"));
+/// ```
+pub fn set_line_safe(enabled: bool) {
+    WRITER
+        .lock()
+        .expect("Global writer lock poisoned - cannot guarantee data consistency")
+        .config
+        .line_safe = enabled;
+}
+
+/// Collapse lines that streamed commands redraw in place, or stop collapsing them with `None`
+///
+/// See [crate::Redraws] for details. Calling [set_writer] preserves the setting.
+///
+/// ```
+/// use bullet_stream::{global::{self, print}, Redraws};
+/// use std::io::Write;
+///
+/// let output = global::with_locked_writer(Vec::<u8>::new(), || {
+///     global::set_redraws(Redraws::Final);
+///     print::sub_stream_with("Downloading", |mut stdout, _| {
+///         write!(stdout, "10%\r50%\r100%\n")
+///     })
+///     .unwrap();
+/// });
+/// assert!(bullet_stream::strip_ansi(String::from_utf8_lossy(&output))
+///     .contains("\n      100%\n"));
+/// ```
+pub fn set_redraws(redraws: impl Into<Option<Redraws>>) {
+    WRITER
+        .lock()
+        .expect("Global writer lock poisoned - cannot guarantee data consistency")
+        .config
+        .redraws = redraws.into();
+}
+
+/// Set how much detail is printed by the global writer
+///
+/// See [crate::Verbosity] for details. Calling [set_writer] preserves the verbosity.
+///
+/// ```
+/// use bullet_stream::{global::{self, print}, Verbosity};
+///
+/// let output = global::with_locked_writer(Vec::<u8>::new(), || {
+///     global::set_verbosity(Verbosity::Debug);
+///     print::sub_bullet_at(Verbosity::Debug, "Cache key abc123");
+/// });
+/// assert_eq!("  - Cache key abc123\n", bullet_stream::strip_ansi(String::from_utf8_lossy(&output)));
+/// ```
+pub fn set_verbosity(verbosity: Verbosity) {
+    WRITER
+        .lock()
+        .expect("Global writer lock poisoned - cannot guarantee data consistency")
+        .config
+        .verbosity = verbosity;
+}
+
+/// Set the colors used by the global writer and the [crate::style] helper functions
+///
+/// Calling [set_writer] preserves the theme.
+///
+/// ```
+/// use bullet_stream::{global::{self, print}, style::{self, Theme}, ColorChoice};
+///
+/// let output = global::with_locked_writer(Vec::<u8>::new(), || {
+///     global::set_color(ColorChoice::Always);
+///     global::set_theme(Theme::default().value("1;34"));
+///     print::bullet(format!("Using {}", style::value("3.4.1")));
+/// });
+/// assert_eq!("- Using `\u{1b}[1;34m3.4.1\u{1b}[0m`\n", String::from_utf8_lossy(&output));
+/// ```
+pub fn set_theme(theme: Theme) {
+    let theme = Arc::new(theme);
+    let mut writer = WRITER
+        .lock()
+        .expect("Global writer lock poisoned - cannot guarantee data consistency");
+    writer.config.theme = theme.clone();
+    replace_theme(theme);
+}
+
+/// Send a structured copy of everything written to the global writer to a sink
+///
+/// See the [crate::event] module for details. Calling [set_writer] preserves registered sinks.
+///
+/// ```
+/// use bullet_stream::{event::{Event, EventSink}, global::{self, print}};
+/// use std::sync::{Arc, Mutex};
+/// use std::time::SystemTime;
+///
+/// #[derive(Clone, Default)]
+/// struct Collect(Arc<Mutex<Vec<Event>>>);
+/// impl EventSink for Collect {
+///     fn event(&mut self, _at: SystemTime, event: &Event) {
+///         self.0.lock().unwrap().push(event.clone());
+///     }
+/// }
+///
+/// let events = Collect::default();
+/// global::with_locked_writer(Vec::<u8>::new(), || {
+///     global::add_event_sink(events.clone());
+///     print::bullet("Hello");
+/// });
+/// assert_eq!(vec![Event::Bullet("Hello".to_string())], *events.0.lock().unwrap());
+/// ```
+pub fn add_event_sink(sink: impl EventSink + 'static) {
+    WRITER
+        .lock()
+        .expect("Global writer lock poisoned - cannot guarantee data consistency")
+        .config
+        .events
+        .push(sink);
+}
+
+/// Change the format of the global writer
+///
+/// See the [crate::render] module for details. Calling [set_writer] preserves the renderer.
+pub fn set_renderer(renderer: impl Renderer + 'static) {
+    WRITER
+        .lock()
+        .expect("Global writer lock poisoned - cannot guarantee data consistency")
+        .config
+        .set_renderer(Arc::new(renderer));
+}
+
+/// Group the output of the global writer for a CI provider, or stop grouping it with `None`
+///
+/// Wraps the renderer from [set_renderer], whether it's set before or after. CI is never
+/// detected on its own, pass [Ci::from_env] to enable it only when running in CI. See the
+/// [crate::ci] module for details.
+///
+/// ```
+/// use bullet_stream::{ci::Ci, global::{self, print}};
+///
+/// let output = global::with_locked_writer(Vec::<u8>::new(), || {
+///     global::set_ci(Ci::GitHubActions);
+///     print::bullet("Ruby version");
+///     print::error("Oops");
+/// });
+/// assert!(String::from_utf8_lossy(&output).starts_with("::group::- Ruby version\n::error::Oops\n"));
+/// ```
+pub fn set_ci(ci: impl Into<Option<Ci>>) {
+    WRITER
+        .lock()
+        .expect("Global writer lock poisoned - cannot guarantee data consistency")
+        .config
+        .set_ci(ci.into());
+}
+
+/// The theme of the global writer, without locking the writer
+pub(crate) fn theme() -> Arc<Theme> {
+    THEME
+        .read()
+        .expect("Global theme lock poisoned - cannot guarantee data consistency")
+        .clone()
+}
+
+fn replace_theme(theme: Arc<Theme>) -> Arc<Theme> {
+    std::mem::replace(
+        &mut *THEME
+            .write()
+            .expect("Global theme lock poisoned - cannot guarantee data consistency"),
+        theme,
+    )
 }
 
 static WITH_WRITER_GLOBAL_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| ().into());
@@ -191,14 +493,13 @@ where
             let mut write_lock = WRITER
                 .lock()
                 .expect("Global writer lock poisoned - cannot guarantee data consistency");
-            std::mem::replace(
-                &mut *write_lock,
-                Box::new(ParagraphInspectWrite::new(new_writer)),
-            )
+            std::mem::replace(&mut *write_lock, boxed_writer(new_writer))
         };
+        let old_theme = replace_theme(Arc::default());
 
         let f_panic = catch_unwind(AssertUnwindSafe(f));
 
+        replace_theme(old_theme);
         let new_writer = {
             let mut write_lock = WRITER
                 .lock()
@@ -206,8 +507,8 @@ where
             std::mem::replace(&mut *write_lock, old_writer)
         };
 
-        if let Ok(original) = (new_writer as Box<dyn Any>).downcast::<ParagraphInspectWrite<W>>() {
-            f_panic.map(|_| original.inner)
+        if let Ok(original) = (new_writer.inner as Box<dyn Any>).downcast::<W>() {
+            f_panic.map(|_| *original)
         } else {
             panic!("Could not downcast to original type. Writer was mutated unexpectedly. This indicates a bug in with_locked_writer implementation.")
         }
@@ -239,8 +540,13 @@ pub mod print {
     //! ```
 
     use super::*;
+    use crate::parallel::Labelled;
+    use crate::progress::{Finishing, ProgressBar, ProgressRead, ProgressWrite};
+    use crate::timer::TimerOptions;
     use crate::write;
     use crate::GlobalTimer;
+    use crate::Timestamps;
+    use std::io::Read;
     use std::time::Instant;
 
     /// Output a h1 header to the global writer without state
@@ -481,6 +787,19 @@ pub mod print {
         write::sub_bullet(&mut GlobalWriter, s);
     }
 
+    /// Print a sub-bullet when the verbosity of the global writer is at least `level`
+    ///
+    /// See [crate::global::set_verbosity].
+    ///
+    /// ```
+    /// use bullet_stream::{global::print, Verbosity};
+    ///
+    /// print::sub_bullet_at(Verbosity::Debug, "Cache key abc123");
+    /// ```
+    pub fn sub_bullet_at(level: Verbosity, s: impl AsRef<str>) {
+        write::sub_bullet_at(&mut GlobalWriter, level, s);
+    }
+
     /// Print a sub-bullet and stream a command to the global writer without state
     ///
     /// ```no_run
@@ -499,7 +818,31 @@ pub mod print {
         F: FnMut(Box<dyn Write + Send + Sync>, Box<dyn Write + Send + Sync>) -> T,
         T: 'static,
     {
-        write::sub_stream_with(&mut GlobalWriter, s, f)
+        write::sub_stream_with(&mut GlobalWriter, s, None, f, |_| false)
+    }
+
+    /// Print a sub-bullet and stream to the global writer, prefixing each line with a timestamp
+    ///
+    /// ```no_run
+    /// use bullet_stream::{global::print, Timestamps};
+    /// use fun_run::CommandWithName;
+    ///
+    /// let mut cmd = std::process::Command::new("bash");
+    /// cmd.args(["-c", "echo 'hello world'"]);
+    ///
+    /// print::sub_stream_with_timestamps(
+    ///     format!("Running {}", cmd.name()),
+    ///     Timestamps::WallClock,
+    ///     |stdout, stderr| cmd.stream_output(stdout, stderr),
+    /// )
+    /// .unwrap();
+    /// ```
+    pub fn sub_stream_with_timestamps<F, T>(s: impl AsRef<str>, timestamps: Timestamps, f: F) -> T
+    where
+        F: FnMut(Box<dyn Write + Send + Sync>, Box<dyn Write + Send + Sync>) -> T,
+        T: 'static,
+    {
+        write::sub_stream_with(&mut GlobalWriter, s, Some(timestamps), f, |_| false)
     }
 
     /// Print the name of a command then stream it
@@ -522,6 +865,73 @@ pub mod print {
         write::sub_stream_cmd(&mut GlobalWriter, command)
     }
 
+    /// Print the name of a command then stream only the last `lines` lines of its output
+    ///
+    /// See [`crate::Print::stream_cmd_tail`] for how the lines are shown. The full output is
+    /// captured in the returned value.
+    ///
+    /// ```no_run
+    /// use bullet_stream::global::print;
+    ///
+    /// print::sub_stream_cmd_tail(
+    ///     std::process::Command::new("bash")
+    ///         .args(["-c", "seq 1 100000"]),
+    ///     20,
+    /// ).unwrap();
+    /// ```
+    #[cfg(feature = "fun_run")]
+    pub fn sub_stream_cmd_tail(
+        command: impl fun_run::CommandWithName,
+        lines: usize,
+    ) -> Result<fun_run::NamedOutput, fun_run::CmdError> {
+        write::sub_stream_cmd_tail(&mut GlobalWriter, command, lines)
+    }
+
+    /// Render a recorded session to the global writer, see the [`crate::record`] module
+    pub fn replay(recording: &crate::record::Recording, playback: crate::record::Playback) {
+        crate::record::replay(&mut GlobalWriter, recording, playback);
+    }
+
+    /// Print a sub-bullet and stream several sources at the same time to the global writer
+    ///
+    /// Each line is prefixed with the label of its source. See
+    /// [`crate::Print::stream_many_with`].
+    pub fn sub_stream_many_with<F, T>(s: impl AsRef<str>, streams: Vec<Labelled<F>>) -> Vec<T>
+    where
+        F: FnOnce(Box<dyn Write + Send + Sync>, Box<dyn Write + Send + Sync>) -> T + Send,
+        T: Send + 'static,
+    {
+        write::sub_stream_many_with(&mut GlobalWriter, s, streams, |_| None, |_| false)
+    }
+
+    /// Print a sub-bullet and run several commands at the same time while streaming their
+    /// output to the global writer
+    ///
+    /// ```no_run
+    /// use bullet_stream::{global::print, parallel::Labelled};
+    /// use std::process::Command;
+    ///
+    /// let mut web = Command::new("npm");
+    /// web.args(["install", "--workspace", "web"]);
+    /// let mut api = Command::new("npm");
+    /// api.args(["install", "--workspace", "api"]);
+    ///
+    /// print::sub_stream_cmds(
+    ///     "Running npm install",
+    ///     vec![Labelled::new("web", web), Labelled::new("api", api)],
+    /// );
+    /// ```
+    #[cfg(feature = "fun_run")]
+    pub fn sub_stream_cmds<C>(
+        s: impl AsRef<str>,
+        commands: Vec<Labelled<C>>,
+    ) -> Vec<Result<fun_run::NamedOutput, fun_run::CmdError>>
+    where
+        C: fun_run::CommandWithName + Send,
+    {
+        write::sub_stream_cmds(&mut GlobalWriter, s, commands)
+    }
+
     /// Print a sub-bullet and then emmit dots to the global writer without state
     ///
     /// ```
@@ -543,12 +953,87 @@ pub mod print {
     /// assert_eq!(expected, bullet_stream::strip_ansi(String::from_utf8_lossy(&output)));
     /// ```
     pub fn sub_start_timer(s: impl AsRef<str>) -> crate::GlobalTimer {
+        sub_start_timer_with(s, TimerOptions::default())
+    }
+
+    /// Print a sub-bullet and then emmit ticks or a spinner to the global writer without state
+    ///
+    /// See [crate::timer::TimerOptions] for details.
+    ///
+    /// ```
+    /// use bullet_stream::{global::print, timer::TimerOptions};
+    /// # use pretty_assertions::assert_eq;
+    /// #
+    /// # let output = bullet_stream::global::with_locked_writer(Vec::<u8>::new(), ||{
+    ///
+    /// print::bullet("Ruby");
+    /// let timer = print::sub_start_timer_with(
+    ///     "Installing",
+    ///     TimerOptions::default().ticks(" [", "#", "] "),
+    /// );
+    /// // ...
+    /// timer.done();
+    /// # });
+    ///
+    /// let expected = indoc::formatdoc!{"
+    ///   - Ruby
+    ///     - Installing [#] (< 0.1s)
+    /// "};
+    /// assert_eq!(expected, bullet_stream::strip_ansi(String::from_utf8_lossy(&output)));
+    /// ```
+    pub fn sub_start_timer_with(s: impl AsRef<str>, options: TimerOptions) -> crate::GlobalTimer {
         let started = Instant::now();
-        let guard = write::sub_start_print_interval(GlobalWriter, s);
+        let (ticks, tick, wrap) = write::timer_start_with(&mut GlobalWriter, s, &options);
+        let guard =
+            write::sub_start_print_interval(GlobalWriter, options.interval, ticks, tick, wrap);
 
         GlobalTimer { started, guard }
     }
 
+    /// Print a sub-bullet and then progress towards a known total to the global writer
+    ///
+    /// See [crate::GlobalProgress] for details.
+    pub fn sub_start_progress(s: impl AsRef<str>, total: u64) -> crate::GlobalProgress {
+        crate::GlobalProgress {
+            bar: ProgressBar::start(&mut GlobalWriter, s, total),
+        }
+    }
+
+    /// Print a sub-bullet and report bytes read from `read` as progress towards `total`
+    ///
+    /// The progress output is finished when the end of the input is reached or the reader is
+    /// dropped.
+    ///
+    /// ```
+    /// use bullet_stream::global::print;
+    ///
+    /// # let output = bullet_stream::global::with_locked_writer(Vec::new(), ||{
+    /// let response = std::io::Read::take(std::io::repeat(b'a'), 2048);
+    /// let mut file = Vec::new();
+    /// std::io::copy(&mut print::sub_progress_reader("Downloading", response, 2048), &mut file).unwrap();
+    /// # });
+    /// let expected = "  - Downloading ............ (< 0.1s)\n";
+    /// # assert_eq!(expected.to_string(), bullet_stream::strip_ansi(String::from_utf8_lossy(&output)));
+    /// ```
+    pub fn sub_progress_reader<R: Read>(
+        s: impl AsRef<str>,
+        read: R,
+        total: u64,
+    ) -> ProgressRead<'static, R> {
+        ProgressRead::new(read, Finishing(Some(sub_start_progress(s, total))))
+    }
+
+    /// Print a sub-bullet and report bytes written to `write` as progress towards `total`
+    ///
+    /// The progress output is finished when the writer is dropped.
+    pub fn sub_progress_writer<W: Write>(
+        s: impl AsRef<str>,
+        write: W,
+        total: u64,
+    ) -> ProgressWrite<'static, W> {
+        ProgressWrite::new(write, Finishing(Some(sub_start_progress(s, total))))
+    }
+
     /// Prints the name of a command and times (with dots) it in the background
     ///
     /// Does not show the output of the command. If you need that use [sub_stream_cmd]
@@ -569,7 +1054,27 @@ pub mod print {
     pub fn sub_time_cmd(
         command: impl fun_run::CommandWithName,
     ) -> Result<fun_run::NamedOutput, fun_run::CmdError> {
-        write::sub_time_cmd(ParagraphInspectWrite::new(GlobalWriter), command)
+        write::sub_time_cmd(&mut GlobalWriter, command)
+    }
+
+    /// Prints the name of a command and times (with dots) it, the output is only shown if it fails
+    ///
+    /// When the command exits with a non-zero status its output is printed like [sub_stream_cmd],
+    /// followed by the error. Otherwise it's the same as [sub_time_cmd].
+    ///
+    /// ```no_run
+    /// use bullet_stream::global::print;
+    ///
+    /// print::sub_time_cmd_stream_on_failure(
+    ///     std::process::Command::new("bash")
+    ///         .args(["-c", "echo 'hello world'"])
+    /// ).unwrap();
+    /// ```
+    #[cfg(feature = "fun_run")]
+    pub fn sub_time_cmd_stream_on_failure(
+        command: impl fun_run::CommandWithName,
+    ) -> Result<fun_run::NamedOutput, fun_run::CmdError> {
+        write::sub_time_cmd_stream_on_failure(&mut GlobalWriter, command)
     }
 
     /// Print an all done message with timing info to the UI
@@ -705,4 +1210,14 @@ mod test {
         let expected = "- Main thread still works\n";
         assert_eq!(expected, strip_ansi(String::from_utf8_lossy(&output)));
     }
+
+    #[test]
+    fn style_helpers_do_not_wait_on_the_writer() {
+        let output = with_locked_writer(Vec::new(), || {
+            set_theme(Theme::default().value("1;34"));
+            let _writer = WRITER.lock().unwrap();
+            assert_eq!("`\x1B[1;34m3.4.1\x1B[0m`", crate::style::value("3.4.1"));
+        });
+        assert!(output.is_empty());
+    }
 }
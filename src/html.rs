@@ -0,0 +1,514 @@
+//! Export output as a standalone HTML page
+//!
+//! The [`HtmlReport`] event sink writes an HTML document alongside the regular output, suitable
+//! for publishing build logs to a web UI:
+//!
+//! - Each part of the output has a CSS class for its role such as `warning`, `value`, or `duration`
+//! - Each bullet and each stream is a collapsible `<details>` section
+//! - Timer dots are kept in a `dim` span followed by the duration
+//! - Only `http` and `https` urls become links
+//!
+//! ```
+//! use bullet_stream::{html::HtmlReport, style, Print};
+//!
+//! let file = tempfile::NamedTempFile::new().unwrap();
+//! Print::new(std::io::sink())
+//!     .with_events(HtmlReport::new(file.reopen().unwrap()).with_title("Example Buildpack"))
+//!     .h2("Example Buildpack")
+//!     .bullet("Ruby version")
+//!     .sub_bullet(format!("Using {}", style::value("3.4.1")))
+//!     .done()
+//!     .done();
+//!
+//! let html = std::fs::read_to_string(file.path()).unwrap();
+//! assert!(html.starts_with("<!DOCTYPE html>"));
+//! assert!(html.contains(r#"<li class="sub-bullet">Using `<span class="value">3.4.1</span>`</li>"#));
+//! assert!(html.ends_with("</html>\n"));
+//! ```
+use crate::ansi_escape::{escape_sequences, strip_ansi, ANSI};
+use crate::duration_format;
+use crate::event::{Event, EventSink};
+use crate::style::Theme;
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+
+const RESET: &str = "\x1B[0m";
+/// Timer dots, the number of ticks isn't part of an event
+const DOTS: &str = r#"<span class="dim"> ... </span>"#;
+
+/// Default styles, matching the colors of the default [`Theme`]
+const STYLE: &str = "
+body { background: #1e1e1e; color: #d4d4d4; font-family: ui-monospace, monospace; }
+h1, h2 { color: #c586c0; }
+ul { list-style: none; margin: 0; padding-left: 2ch; }
+summary { cursor: pointer; }
+pre.stream-output { margin: 0.5em 0 0.5em 4ch; }
+.warning, .value { color: #dcdcaa; }
+.error { color: #f48771; }
+.important, .command { color: #4fc1ff; font-weight: bold; }
+.url { color: #4fc1ff; font-weight: bold; text-decoration: underline; }
+.dim { opacity: 0.6; }
+";
+
+/// Writes events as an HTML document.
+///
+/// The document is written as events arrive and finished on [`Event::AllDone`]. If output stops
+/// before then, open sections are closed and the document is finished when the sink is dropped.
+#[derive(Debug)]
+pub struct HtmlReport<W: Write> {
+    write: W,
+    title: String,
+    classes: Vec<(String, &'static str)>,
+    started: bool,
+    finished: bool,
+    bullet_open: bool,
+    list_open: bool,
+    /// Output is only written inside of a stream
+    stream_open: bool,
+    timer: Option<String>,
+}
+
+impl<W: Write> HtmlReport<W> {
+    pub fn new(write: W) -> Self {
+        Self {
+            write,
+            title: String::from("Build output"),
+            classes: classes(&Theme::default()),
+            started: false,
+            finished: false,
+            bullet_open: false,
+            list_open: false,
+            stream_open: false,
+            timer: None,
+        }
+    }
+
+    /// The `<title>` of the page. Default: `Build output`
+    #[must_use]
+    pub fn with_title(mut self, title: impl AsRef<str>) -> Self {
+        self.title = title.as_ref().to_string();
+        self
+    }
+
+    /// The theme used to detect text styled with the [`crate::style`] helpers
+    ///
+    /// Only needed if the theme was changed with [`crate::global::set_theme`].
+    #[must_use]
+    pub fn with_theme(mut self, theme: &Theme) -> Self {
+        self.classes = classes(theme);
+        self
+    }
+
+    /// Convert styled text to HTML with a `<span>` per styled part
+    fn text(&self, contents: &str) -> String {
+        let mut result = String::new();
+        let mut class: Option<&str> = None;
+        let mut close: Option<&str> = None;
+        let mut rest = contents.trim();
+        while !rest.is_empty() {
+            let sequence = escape_sequences(rest)
+                .into_iter()
+                .next()
+                .filter(|sequence| sequence.start == 0);
+            if let Some(sequence) = sequence {
+                let escape = &rest[sequence.clone()];
+                rest = &rest[sequence.end..];
+                if let Some(close) = close.take() {
+                    result.push_str(close);
+                }
+                class = if escape == RESET {
+                    None
+                } else {
+                    self.classes
+                        .iter()
+                        .find(|(known, _)| known == escape)
+                        .map(|(_, class)| *class)
+                };
+            } else {
+                let end = rest.find('\x1B').unwrap_or(rest.len()).max(1);
+                let part = escape(&rest[..end]);
+                match (class, close) {
+                    (Some("url"), None) if is_web_url(&rest[..end]) => {
+                        result.push_str(&format!(r#"<a class="url" href="{part}">"#));
+                        close = Some("</a>");
+                    }
+                    (Some(class), None) => {
+                        result.push_str(&format!(r#"<span class="{class}">"#));
+                        close = Some("</span>");
+                    }
+                    _ => {}
+                }
+                result.push_str(&part);
+                rest = &rest[end..];
+            }
+        }
+        if let Some(close) = close {
+            result.push_str(close);
+        }
+        result
+    }
+
+    fn write(&mut self, contents: impl AsRef<str>) {
+        self.start();
+        writeln!(self.write, "{}", contents.as_ref()).expect("HTML writer not to be closed");
+    }
+
+    /// Write the head of the document before anything else
+    fn start(&mut self) {
+        if !self.started {
+            self.started = true;
+            let title = escape(&self.title);
+            writeln!(
+                self.write,
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>"
+            )
+            .expect("HTML writer not to be closed");
+        }
+    }
+
+    fn open_list(&mut self) {
+        if !self.list_open {
+            self.list_open = true;
+            self.write("<ul>");
+        }
+    }
+
+    fn close_bullet(&mut self) {
+        let tags = self.bullet_closing_tags();
+        if !tags.is_empty() {
+            self.write(tags.trim_end());
+        }
+    }
+
+    /// Tags that close the current bullet, one per line
+    fn bullet_closing_tags(&mut self) -> String {
+        let mut tags = String::new();
+        if self.list_open {
+            self.list_open = false;
+            tags.push_str("</ul>\n");
+        }
+        if self.bullet_open {
+            self.bullet_open = false;
+            tags.push_str("</details>\n");
+        }
+        tags
+    }
+
+    /// Tags that close every open section and the document
+    fn closing_tags(&mut self) -> String {
+        let mut tags = String::new();
+        if self.stream_open {
+            self.stream_open = false;
+            tags.push_str("</pre></details></li>\n");
+        }
+        tags.push_str(&self.bullet_closing_tags());
+        tags.push_str("</body>\n</html>\n");
+        tags
+    }
+
+    fn block(&mut self, contents: String) {
+        self.close_bullet();
+        self.write(contents);
+    }
+
+    fn paragraph(&mut self, class: &str, contents: &str) {
+        let contents = self
+            .text(contents)
+            .split("\n\n")
+            .map(|paragraph| format!("<p>{}</p>", paragraph.replace('\n', "<br>\n")))
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.block(format!(r#"<div class="{class}">{contents}</div>"#));
+    }
+
+    fn item(&mut self, class: &str, contents: String) {
+        self.open_list();
+        self.write(format!(r#"<li class="{class}">{contents}</li>"#));
+    }
+
+    fn timer(&mut self, details: String) {
+        let text = self.timer.take().unwrap_or_default();
+        self.item("timer", format!("{text}{details}"));
+    }
+
+    fn finish(&mut self) {
+        if !self.finished {
+            self.finished = true;
+            let tags = self.closing_tags();
+            self.write(tags.trim_end());
+        }
+    }
+}
+
+impl<W: Write + Send> EventSink for HtmlReport<W> {
+    fn event(&mut self, _at: SystemTime, event: &Event) {
+        if self.finished {
+            return;
+        }
+        match event {
+            Event::H1(s) => self.block(format!(r#"<h1 class="h1">{}</h1>"#, self.text(s))),
+            Event::H2(s) => self.block(format!(r#"<h2 class="h2">{}</h2>"#, self.text(s))),
+            Event::H3(s) => self.block(format!(r#"<h3 class="h3">{}</h3>"#, self.text(s))),
+            Event::Plain(s) => {
+                if !s.trim().is_empty() {
+                    self.paragraph("plain", s);
+                }
+            }
+            Event::Warning(s) => self.paragraph("warning", s),
+            Event::Error(s) => self.paragraph("error", s),
+            Event::Important(s) => self.paragraph("important", s),
+            Event::Bullet(s) => {
+                self.close_bullet();
+                self.bullet_open = true;
+                let summary = self.text(s).replace('\n', "<br>\n");
+                self.write(format!(
+                    r#"<details class="bullet" open><summary>{summary}</summary>"#
+                ));
+            }
+            Event::NestedBullet { depth, text } => {
+                let contents = self.text(text).replace('\n', "<br>\n");
+                self.open_list();
+                self.write(format!(
+                    r#"<li class="nested" style="padding-left: {}ch">{contents}</li>"#,
+                    2 * (depth.saturating_sub(Event::NESTED_BULLET_DEPTH) + 1)
+                ));
+            }
+            Event::BulletDone => self.close_bullet(),
+            Event::SubBullet(s) => {
+                let contents = self.text(s).replace('\n', "<br>\n");
+                self.item("sub-bullet", contents);
+            }
+            Event::StreamStart(s) => {
+                self.stream_open = true;
+                self.open_list();
+                let summary = self.text(s);
+                self.write(format!(
+                    r#"<li class="stream"><details open><summary>{summary}</summary><pre class="stream-output">"#
+                ));
+            }
+            Event::StreamOutput(line) => {
+                // Output of a stream that started before the sink was added has nowhere to go
+                if self.stream_open {
+                    let line = escape(&strip_ansi(line));
+                    write!(self.write, "{line}").expect("HTML writer not to be closed");
+                }
+            }
+            Event::StreamDone(_) | Event::StreamsDone { .. } if !self.stream_open => {}
+            Event::StreamDone(duration) => {
+                self.stream_open = false;
+                self.write(format!(
+                    r#"</pre></details><span class="stream-done">Done ({})</span></li>"#,
+                    html_duration(duration)
+                ));
+            }
+            Event::StreamsDone { summary, .. } => {
+                self.stream_open = false;
+                let summary = self.text(summary);
+                self.write(format!(
+                    r#"</pre></details><span class="stream-done">Done ({summary})</span></li>"#
+                ));
+            }
+            Event::TimerStart(s) => self.timer = Some(self.text(s)),
+            Event::TimerDone(duration) => {
+                let details = format!("{DOTS}({})", html_duration(duration));
+                self.timer(details);
+            }
+            Event::TimerCancel(why) => {
+                let details = format!(r#"{DOTS}(<span class="cancel">{}</span>)"#, self.text(why));
+                self.timer(details);
+            }
+            Event::AllDone(duration) => {
+                let contents = match duration {
+                    Some(duration) => format!("Done (finished in {})", html_duration(duration)),
+                    None => String::from("Done"),
+                };
+                self.block(format!(r#"<div class="all-done">{contents}</div>"#));
+                self.finish();
+            }
+        }
+        self.write.flush().expect("HTML writer not to be closed");
+    }
+}
+
+impl<W: Write> Drop for HtmlReport<W> {
+    fn drop(&mut self) {
+        if self.started && !self.finished {
+            self.finished = true;
+            let tags = self.closing_tags();
+            // Drop implementations must not panic. We intentionally ignore the potential error here.
+            let _result = self.write.write_all(tags.as_bytes());
+        }
+    }
+}
+
+fn classes(theme: &Theme) -> Vec<(String, &'static str)> {
+    [
+        (&theme.url, "url"),
+        (&theme.command, "command"),
+        (&theme.value, "value"),
+        (&theme.dim, "dim"),
+        (&theme.warning, "warning"),
+        (&theme.error, "error"),
+        (&theme.important, "important"),
+    ]
+    .into_iter()
+    .filter(|(ansi, _)| !ansi.to_str().is_empty())
+    .map(|(ansi, class): (&ANSI, _)| (ansi.to_str().to_string(), class))
+    .collect()
+}
+
+fn html_duration(duration: &Duration) -> String {
+    format!(
+        r#"<span class="duration">{}</span>"#,
+        escape(&duration_format::human(duration))
+    )
+}
+
+/// Links to other schemes such as `javascript:` are not safe to follow
+fn is_web_url(url: &str) -> bool {
+    let url = url.trim_start().to_ascii_lowercase();
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+fn escape(contents: &str) -> String {
+    contents
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::style;
+    use crate::util::LockedWriter;
+    use indoc::formatdoc;
+    use pretty_assertions::assert_eq;
+
+    fn report(events: Vec<Event>) -> String {
+        let mut sink = HtmlReport::new(Vec::new());
+        for event in events {
+            sink.event(SystemTime::now(), &event);
+        }
+        let html = String::from_utf8(std::mem::take(&mut sink.write)).unwrap();
+        let body = html.split_once("<body>\n").unwrap().1;
+        body.to_string()
+    }
+
+    #[test]
+    fn full_report() {
+        let actual = report(vec![
+            Event::H2("Example <Buildpack>".to_string()),
+            Event::Bullet("Ruby version".to_string()),
+            Event::SubBullet(format!("Using {}", style::value("3.4.1"))),
+            Event::TimerStart("Downloading".to_string()),
+            Event::TimerDone(Duration::from_millis(1200)),
+            Event::StreamStart(style::running_command("bundle install")),
+            Event::StreamOutput("\x1B[0;32mBundle complete!\x1B[0m\n".to_string()),
+            Event::StreamDone(Duration::from_secs(3)),
+            Event::Warning(format!(
+                "Careful\n\nSee {}",
+                style::url("https://example.com")
+            )),
+            Event::AllDone(Some(Duration::from_secs(5))),
+        ]);
+
+        assert_eq!(
+            formatdoc! {r#"
+                <h2 class="h2">Example &lt;Buildpack&gt;</h2>
+                <details class="bullet" open><summary>Ruby version</summary>
+                <ul>
+                <li class="sub-bullet">Using `<span class="value">3.4.1</span>`</li>
+                <li class="timer">Downloading<span class="dim"> ... </span>(<span class="duration">1.2s</span>)</li>
+                <li class="stream"><details open><summary>Running `<span class="command">bundle install</span>`</summary><pre class="stream-output">
+                Bundle complete!
+                </pre></details><span class="stream-done">Done (<span class="duration">3.0s</span>)</span></li>
+                </ul>
+                </details>
+                <div class="warning"><p>Careful</p>
+                <p>See <a class="url" href="https://example.com">https://example.com</a></p></div>
+                <div class="all-done">Done (finished in <span class="duration">5.0s</span>)</div>
+                </body>
+                </html>
+            "#},
+            actual
+        );
+    }
+
+    #[test]
+    fn timers_keep_dots_and_the_duration() {
+        let actual = report(vec![
+            Event::TimerStart("Compiling".to_string()),
+            Event::TimerDone(Duration::from_secs(20 * 60)),
+            Event::TimerStart("Retrying".to_string()),
+            Event::TimerCancel("Interrupted".to_string()),
+        ]);
+        assert!(actual.contains(
+            r#"<li class="timer">Compiling<span class="dim"> ... </span>(<span class="duration">20m 0s</span>)</li>"#
+        ));
+        assert!(actual.contains(
+            r#"<li class="timer">Retrying<span class="dim"> ... </span>(<span class="cancel">Interrupted</span>)</li>"#
+        ));
+    }
+
+    #[test]
+    fn keeps_text_after_other_escape_sequences() {
+        let actual = report(vec![Event::Plain(format!(
+            "\x1B[2Kerased {} and \x1B[0Kmore",
+            style::value("3.4.1")
+        ))]);
+        assert!(
+            actual.contains(r#"<p>erased `<span class="value">3.4.1</span>` and more</p>"#),
+            "{actual}"
+        );
+    }
+
+    #[test]
+    fn closes_open_sections_when_dropped() {
+        let write = LockedWriter::new(Vec::new());
+        let mut sink = HtmlReport::new(write.clone());
+        for event in [
+            Event::Bullet("Ruby".to_string()),
+            Event::StreamStart("Installing".to_string()),
+            Event::StreamOutput("partial\n".to_string()),
+        ] {
+            sink.event(SystemTime::now(), &event);
+        }
+        drop(sink);
+
+        let html = String::from_utf8(write.unwrap()).unwrap();
+        assert!(
+            html.ends_with("partial\n</pre></details></li>\n</ul>\n</details>\n</body>\n</html>\n"),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn links_only_web_urls() {
+        let actual = report(vec![Event::Plain(format!(
+            "{} {}",
+            style::url("javascript:alert(1)"),
+            style::url("HTTPS://example.com")
+        ))]);
+        assert!(actual.contains(r#"<span class="url">javascript:alert(1)</span>"#));
+        assert!(actual.contains(r#"<a class="url" href="HTTPS://example.com">"#));
+        assert!(!actual.contains(r#"href="javascript"#), "{actual}");
+    }
+
+    #[test]
+    fn ignores_output_outside_of_a_stream() {
+        let mut sink = HtmlReport::new(Vec::new());
+        for event in [
+            Event::StreamOutput("orphan\n".to_string()),
+            Event::StreamDone(Duration::from_secs(1)),
+            Event::Bullet("Ruby".to_string()),
+        ] {
+            sink.event(SystemTime::now(), &event);
+        }
+        let html = String::from_utf8(std::mem::take(&mut sink.write)).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"), "{html}");
+        assert!(!html.contains("orphan"), "{html}");
+        assert!(!html.contains("stream-done"), "{html}");
+    }
+}
@@ -1,25 +1,46 @@
 #![doc = include_str!("../README.md")]
+use crate::event::Event;
 use crate::util::ParagraphInspectWrite;
-use crate::write::line_mapped;
 use global::GlobalWriter;
 use std::fmt::Debug;
 use std::io::Write;
 use std::time::Instant;
-use style::CMD_INDENT;
-use util::TrailingParagraph;
 
-pub use ansi_escape::strip_ansi;
+pub use ansi_escape::{strip_ansi, ColorChoice};
 #[cfg(feature = "fun_run")]
 pub use fun_run;
+pub use redraw::Redraws;
+pub use tee::Tee;
+pub use text_wrap::Wrapping;
+pub use timestamp::Timestamps;
+pub use verbosity::Verbosity;
 
 mod ansi_escape;
 mod background_printer;
+mod byte_format;
 mod duration_format;
+mod redraw;
+#[cfg(feature = "fun_run")]
+mod tail;
+mod tee;
+mod text_wrap;
+mod timestamp;
 mod util;
+mod verbosity;
 mod write;
 
+pub mod ci;
+pub mod event;
 pub mod global;
+pub mod html;
+pub mod markdown;
+pub mod parallel;
+pub mod progress;
+pub mod record;
+pub mod redact;
+pub mod render;
 pub mod style;
+pub mod timer;
 
 /// Holds a reference to an actively printing timer in the background
 ///
@@ -63,7 +84,10 @@ impl GlobalTimer {
             Err(e) => std::panic::resume_unwind(e),
         };
 
-        writeln_now(&mut io, style::details(why_details));
+        render::render(
+            &mut io,
+            Event::TimerCancel(why_details.as_ref().to_string()),
+        );
     }
 
     /// Finalize a timer's output.
@@ -90,7 +114,48 @@ impl GlobalTimer {
             Err(e) => std::panic::resume_unwind(e),
         };
 
-        writeln_now(&mut io, style::details(duration_format::human(&duration)));
+        render::render(&mut io, Event::TimerDone(duration));
+    }
+}
+
+/// Holds progress towards a known total written to the global writer
+///
+/// ```
+/// use bullet_stream::global::print;
+///
+/// # let output = bullet_stream::global::with_locked_writer(Vec::new(), ||{
+/// let mut progress = print::sub_start_progress("Downloading", 2048);
+/// progress.inc(1024);
+/// progress.inc(1024);
+/// progress.done();
+/// # });
+/// let expected = "  - Downloading ............ (< 0.1s)\n";
+/// # assert_eq!(expected.to_string(), bullet_stream::strip_ansi(String::from_utf8_lossy(&output)));
+/// ```
+#[derive(Debug)]
+pub struct GlobalProgress {
+    pub(crate) bar: progress::ProgressBar,
+}
+
+impl GlobalProgress {
+    /// Add to the amount of progress made, such as the number of bytes downloaded
+    pub fn inc(&mut self, n: u64) {
+        self.bar.inc(&mut GlobalWriter, n);
+    }
+
+    /// Finalize the progress output with the elapsed time
+    pub fn done(self) {
+        self.bar.done(&mut GlobalWriter);
+    }
+
+    /// Report bytes read from `read` as progress, see the [`progress`] module
+    pub fn reader<R: std::io::Read>(&mut self, read: R) -> progress::ProgressRead<'_, R> {
+        progress::ProgressRead::new(read, self)
+    }
+
+    /// Report bytes written to `write` as progress, see the [`progress`] module
+    pub fn writer<T: Write>(&mut self, write: T) -> progress::ProgressWrite<'_, T> {
+        progress::ProgressWrite::new(write, self)
     }
 }
 
@@ -129,6 +194,7 @@ pub type Output<T> = Print<T>;
 /// represent the various states. See struct documentation for more details.
 pub mod state {
     use crate::background_printer::PrintGuard;
+    use crate::redraw::RedrawWrite;
     use crate::util::ParagraphInspectWrite;
     use crate::write::MappedWrite;
     use std::time::Instant;
@@ -232,8 +298,46 @@ pub mod state {
         pub(crate) write: ParagraphInspectWrite<W>,
     }
 
+    /// The `state::Nested` provides details about a sub bullet, for example installing each
+    /// package of a language. It can be nested any number of levels deep, `P` is the state
+    /// that `done()` returns to.
+    ///
+    /// It is started from a `state::SubBullet` or another `state::Nested`.
+    ///
+    /// ```rust
+    /// use bullet_stream::{Print, state::{Nested, SubBullet}};
+    /// use std::io::Write;
+    ///
+    /// let output = Print::new(Vec::new())
+    ///     .without_header()
+    ///     .bullet("Dependencies");
+    ///
+    /// let output = install_packages(output.nested("Installing packages")).done().done().done();
+    ///
+    /// assert_eq!(
+    ///     "- Dependencies\n  - Installing packages\n    - rake\n      - rake.gemspec\n    - rack\n- Done (finished in < 0.1s)\n",
+    ///     String::from_utf8_lossy(&output)
+    /// );
+    ///
+    /// fn install_packages<W>(output: Print<Nested<W, SubBullet<W>>>) -> Print<Nested<W, SubBullet<W>>>
+    /// where W: Write + Send + Sync + 'static {
+    ///     output
+    ///         .nested("rake")
+    ///         .sub_bullet("rake.gemspec")
+    ///         .done()
+    ///         .sub_bullet("rack")
+    /// }
+    /// ```
+    #[derive(Debug)]
+    pub struct Nested<W, P> {
+        pub(crate) write: ParagraphInspectWrite<W>,
+        pub(crate) depth: usize,
+        pub(crate) parent: std::marker::PhantomData<P>,
+    }
+
     /// This state is intended for streaming output from a process to the end user. It is
-    /// started from a `state::SubBullet` and finished back to a `state::SubBullet`.
+    /// started from a `state::SubBullet` or `state::Nested` and finished back to it, `P` is the
+    /// state that `done()` returns to.
     ///
     /// The `Print<state::Stream<W>>` implements [`std::io::Write`], so you can stream
     /// from anything that accepts a [`std::io::Write`].
@@ -259,15 +363,47 @@ pub mod state {
     ///}
     /// ```
     #[derive(Debug)]
-    pub struct Stream<W: std::io::Write> {
+    pub struct Stream<W: std::io::Write, P = SubBullet<W>> {
         pub(crate) started: Instant,
-        pub(crate) write: MappedWrite<ParagraphInspectWrite<W>>,
+        pub(crate) write: RedrawWrite<MappedWrite<ParagraphInspectWrite<W>>>,
+        /// The depth of the state it was started from
+        pub(crate) depth: usize,
+        pub(crate) parent: std::marker::PhantomData<P>,
+    }
+
+    /// This state is intended for tasks with a known amount of work, such as downloading a file
+    /// with a known size. It is started from a [`SubBullet`] and finished back to a [`SubBullet`].
+    ///
+    /// On a terminal the percentage, bytes, and rate are redrawn in place. Otherwise a dot is
+    /// written for every 10% of progress so logs stay append-only.
+    ///
+    /// ```rust
+    /// use bullet_stream::Print;
+    ///
+    /// let mut progress = Print::new(Vec::new())
+    ///     .without_header()
+    ///     .bullet("Ruby version")
+    ///     .start_progress("Downloading", 4096);
+    ///
+    /// for _ in 0..4 {
+    ///     progress.inc(1024);
+    /// }
+    ///
+    /// let output = progress.done().done().done();
+    /// let output = bullet_stream::strip_ansi(String::from_utf8_lossy(&output));
+    /// assert!(output.contains("  - Downloading ............ (< 0.1s)\n"));
+    /// ```
+    #[derive(Debug)]
+    pub struct Progress<W> {
+        pub(crate) write: ParagraphInspectWrite<W>,
+        pub(crate) bar: crate::progress::ProgressBar,
     }
 
     /// This state is intended for long-running tasks that do not stream but wish to convey progress
     /// to the end user. For example, while downloading a file.
     ///
-    /// This state is started from a [`SubBullet`] and finished back to a [`SubBullet`].
+    /// This state is started from a [`SubBullet`] or [`Nested`] and finished back to it, `P` is
+    /// the state that `done()` returns to.
     ///
     /// ```rust
     /// use bullet_stream::{Print, state::{Bullet, SubBullet}};
@@ -290,9 +426,50 @@ pub mod state {
     ///}
     /// ```
     #[derive(Debug)]
-    pub struct Background<W: std::io::Write + Send + 'static> {
+    pub struct Background<W: std::io::Write + Send + 'static, P = SubBullet<W>> {
         pub(crate) started: Instant,
         pub(crate) write: PrintGuard<ParagraphInspectWrite<W>>,
+        /// The depth of the state it was started from
+        pub(crate) depth: usize,
+        pub(crate) parent: std::marker::PhantomData<P>,
+    }
+
+    /// Several timed steps running at the same time.
+    ///
+    /// This state is started from a [`SubBullet`] and finished back to a [`SubBullet`]. See the
+    /// [`crate::parallel`] module for details and an example.
+    #[derive(Debug)]
+    pub struct Parallel<W> {
+        pub(crate) group: crate::parallel::Group<W>,
+    }
+
+    /// Same as [`Background`] but the timer is driven by a `tokio` task instead of a thread.
+    ///
+    /// This state is started from a [`SubBullet`] and finished back to a [`SubBullet`] by awaiting
+    /// `done` or `cancel`.
+    ///
+    /// ```rust
+    /// use bullet_stream::Print;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let timer = Print::new(Vec::new())
+    ///     .without_header()
+    ///     .bullet("Ruby version")
+    ///     .start_timer_async("Installing");
+    ///
+    /// tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+    ///
+    /// let output = timer.done().await.done().done();
+    /// assert!(bullet_stream::strip_ansi(String::from_utf8_lossy(&output))
+    ///     .contains("  - Installing ... (< 0.1s)\n"));
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    #[derive(Debug)]
+    pub struct AsyncBackground<W> {
+        pub(crate) started: Instant,
+        pub(crate) write: crate::background_printer::AsyncPrintGuard<ParagraphInspectWrite<W>>,
     }
 }
 
@@ -303,11 +480,7 @@ impl Print<state::Header<GlobalWriter>> {
     pub fn global() -> Print<state::Header<GlobalWriter>> {
         Print {
             state: state::Header {
-                write: ParagraphInspectWrite {
-                    inner: GlobalWriter,
-                    was_paragraph: GlobalWriter.trailing_paragraph(),
-                    newlines_since_last_char: GlobalWriter.trailing_newline_count(),
-                },
+                write: GlobalWriter::inspect_write(),
             },
             started: None,
         }
@@ -320,6 +493,8 @@ where
 {
     /// Create a buildpack output struct, but do not announce the buildpack's start.
     ///
+    /// Colors are emitted based on [`ColorChoice::Auto`], use [`Print::with_color`] to change it.
+    ///
     /// See the [`Print::h1`] and [`Print::h2`] methods for more details.
     #[must_use]
     pub fn new(io: W) -> Self {
@@ -331,6 +506,159 @@ where
         }
     }
 
+    /// Choose when to emit colors (ANSI escape sequences).
+    ///
+    /// When colors are disabled every color is removed, this includes text styled via the
+    /// [`style`] module. Escape sequences that redraw output on a terminal, such as erasing a
+    /// line, are kept. Otherwise the output is the same as calling [`strip_ansi`] on colored
+    /// output.
+    ///
+    /// ```rust
+    /// use bullet_stream::{style, ColorChoice, Print};
+    ///
+    /// let output = Print::new(Vec::new())
+    ///     .with_color(ColorChoice::Never)
+    ///     .h2("Example Buildpack")
+    ///     .bullet(format!("Ruby version {}", style::value("3.4.1")))
+    ///     .done()
+    ///     .done();
+    ///
+    /// assert!(!String::from_utf8_lossy(&output).contains("\x1B"));
+    /// ```
+    ///
+    /// When using [`Print::global`] configure colors with [`global::set_color`] instead.
+    #[must_use]
+    pub fn with_color(mut self, choice: ColorChoice) -> Self {
+        self.state.write.set_color(choice);
+        self
+    }
+
+    /// Wrap bullets and paragraphs to a width.
+    ///
+    /// Continuation lines keep the indentation of the bullet or the `! ` of a paragraph. See
+    /// [`Wrapping`] for details and an example.
+    ///
+    /// When using [`Print::global`] configure wrapping with [`global::set_wrap`] instead.
+    #[must_use]
+    pub fn with_wrap(mut self, wrapping: Wrapping) -> Self {
+        self.state.write.config.width = wrapping.width(self.state.write.config.is_terminal);
+        self
+    }
+
+    /// Send a structured copy of the output to an [`event::EventSink`].
+    ///
+    /// Can be called multiple times to register multiple sinks. See the [`event`] module for
+    /// details and an example.
+    ///
+    /// When using [`Print::global`] register sinks with [`global::add_event_sink`] instead.
+    #[must_use]
+    pub fn with_events(mut self, sink: impl event::EventSink + 'static) -> Self {
+        self.state.write.config.events.push(sink);
+        self
+    }
+
+    /// Change the format of the output.
+    ///
+    /// See the [`render`] module for details and an example.
+    ///
+    /// When using [`Print::global`] configure the format with [`global::set_renderer`] instead.
+    #[must_use]
+    pub fn with_renderer(mut self, renderer: impl render::Renderer + 'static) -> Self {
+        self.state
+            .write
+            .config
+            .set_renderer(std::sync::Arc::new(renderer));
+        self
+    }
+
+    /// Group the output for a CI provider, or stop grouping it with `None`.
+    ///
+    /// Wraps the renderer from [`Print::with_renderer`], whether it's set before or after. CI is
+    /// never detected on its own, pass [`ci::Ci::from_env`] to enable it only when running in CI.
+    /// See the [`ci`] module for details and an example.
+    ///
+    /// When using [`Print::global`] configure CI groups with [`global::set_ci`] instead.
+    #[must_use]
+    pub fn with_ci(mut self, ci: impl Into<Option<ci::Ci>>) -> Self {
+        self.state.write.config.set_ci(ci.into());
+        self
+    }
+
+    /// Hide secrets such as tokens and passwords from all output.
+    ///
+    /// See the [`redact`] module for details and an example.
+    #[must_use]
+    pub fn with_redactions(mut self, redactions: redact::Redactions) -> Self {
+        self.state.write.config.redactions = std::sync::Arc::new(redactions);
+        self
+    }
+
+    /// Make every line of output stand on its own, such as when it's prefixed with `remote: `
+    /// during a `git push`.
+    ///
+    /// No colors are carried over a newline, they're reset at the end of a line and applied again
+    /// on the next one. Each line streamed from a command is closed on its own, colors it leaves
+    /// open end with the line. Redrawing in place is rewritten into lines that are only ever
+    /// appended: a carriage return starts a new line, other cursor movement is removed, and
+    /// timers and progress print as if the writer was not a terminal.
+    ///
+    /// ```rust
+    /// use bullet_stream::{ColorChoice, Print};
+    /// use std::io::Write;
+    ///
+    /// let mut stream = Print::new(Vec::new())
+    ///     .with_color(ColorChoice::Always)
+    ///     .with_line_safe(true)
+    ///     .without_header()
+    ///     .bullet("Assets")
+    ///     .start_stream("Compiling");
+    /// write!(stream, "\x1B[31mred\n10%\r100%\n").unwrap();
+    ///
+    /// let output = String::from_utf8_lossy(&stream.done().done().done()).into_owned();
+    /// assert!(output.contains("      \x1B[31mred\x1B[0m\n      10%\n      100%\n"));
+    /// ```
+    ///
+    /// Not meant for the [`ci::Ci::GitLab`] renderer, its collapsible sections rely on a carriage
+    /// return.
+    ///
+    /// When using [`Print::global`] configure it with [`global::set_line_safe`] instead.
+    #[must_use]
+    pub fn with_line_safe(mut self, enabled: bool) -> Self {
+        self.state.write.config.line_safe = enabled;
+        self
+    }
+
+    /// Collapse lines that streamed commands redraw in place, such as progress bars.
+    ///
+    /// See [`Redraws`] for details and an example. Passing `None` keeps every redraw (default).
+    ///
+    /// When using [`Print::global`] configure it with [`global::set_redraws`] instead.
+    #[must_use]
+    pub fn with_redraws(mut self, redraws: impl Into<Option<Redraws>>) -> Self {
+        self.state.write.config.redraws = redraws.into();
+        self
+    }
+
+    /// Choose how much detail to print, such as [`Verbosity::from_env`].
+    ///
+    /// See [`Verbosity`] for details and an example.
+    ///
+    /// When using [`Print::global`] configure it with [`global::set_verbosity`] instead.
+    #[must_use]
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.state.write.config.verbosity = verbosity;
+        self
+    }
+
+    /// Change the colors used for headers, paragraphs, and timers.
+    ///
+    /// See [`style::Theme`] for details and an example.
+    #[must_use]
+    pub fn with_theme(mut self, theme: style::Theme) -> Self {
+        self.state.write.config.theme = std::sync::Arc::new(theme);
+        self
+    }
+
     /// Announce the start of the buildpack.
     ///
     /// The input should be the human-readable name of your buildpack. Most buildpack names include
@@ -384,6 +712,15 @@ where
             },
         }
     }
+
+    /// Render a recorded session, see the [`record`] module for an example.
+    ///
+    /// Output is rendered again using the color, wrapping, theme, and renderer of this `Print`,
+    /// so it can differ from what was originally written.
+    pub fn replay(mut self, recording: &record::Recording, playback: record::Playback) -> W {
+        record::replay(&mut self.state.write, recording, playback);
+        self.state.write.inner
+    }
 }
 
 impl<W> Print<state::Bullet<W>>
@@ -497,7 +834,10 @@ where
             Err(e) => std::panic::resume_unwind(e),
         };
 
-        writeln_now(&mut io, style::details(why_details));
+        render::render(
+            &mut io,
+            Event::TimerCancel(why_details.as_ref().to_string()),
+        );
         Print {
             started: self.started,
             state: state::SubBullet { write: io },
@@ -517,7 +857,117 @@ where
             Err(e) => std::panic::resume_unwind(e),
         };
 
-        writeln_now(&mut io, style::details(duration_format::human(&duration)));
+        render::render(&mut io, Event::TimerDone(duration));
+        Print {
+            started: self.started,
+            state: state::SubBullet { write: io },
+        }
+    }
+}
+
+impl<W, P> Print<state::Background<W, P>>
+where
+    W: Write + Send + Sync + 'static,
+{
+    /// Return to `Q` at `depth` once the timer is finished
+    fn reparent<Q>(self, depth: usize) -> Print<state::Background<W, Q>> {
+        Print {
+            started: self.started,
+            state: state::Background {
+                started: self.state.started,
+                write: self.state.write,
+                depth,
+                parent: std::marker::PhantomData,
+            },
+        }
+    }
+}
+
+impl<W, P> Print<state::Background<W, state::Nested<W, P>>>
+where
+    W: Write + Send + Sync + 'static,
+{
+    /// Interrupt a timer with a message explaining why and transition back to
+    /// [`state::Nested`], see [`Print<state::Background>::cancel`].
+    pub fn cancel(self, why_details: impl AsRef<str>) -> Print<state::Nested<W, P>> {
+        let depth = self.state.depth;
+        self.reparent::<state::SubBullet<W>>(Event::SUB_BULLET_DEPTH)
+            .cancel(why_details)
+            .nested_at(depth)
+    }
+
+    /// Finalize a timer's output and transition back to [`state::Nested`].
+    pub fn done(self) -> Print<state::Nested<W, P>> {
+        let depth = self.state.depth;
+        self.reparent::<state::SubBullet<W>>(Event::SUB_BULLET_DEPTH)
+            .done()
+            .nested_at(depth)
+    }
+}
+
+impl<W> Print<state::Parallel<W>>
+where
+    W: Write + Send + Sync + 'static,
+{
+    /// Add a labelled step with its own timer.
+    ///
+    /// The step can be moved to another thread and finished with [`parallel::Step::done`] or
+    /// [`parallel::Step::cancel`].
+    #[must_use]
+    pub fn step(&mut self, label: impl AsRef<str>) -> parallel::Step {
+        self.state.group.step(label)
+    }
+
+    /// Finalize the group and transition back to a [`state::SubBullet`].
+    ///
+    /// Steps that are not finished yet are shown as an error.
+    #[must_use]
+    pub fn done(self) -> Print<state::SubBullet<W>> {
+        Print {
+            started: self.started,
+            state: state::SubBullet {
+                write: self.state.group.done(),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<W> Print<state::AsyncBackground<W>>
+where
+    W: Write + Send + Sync + 'static,
+{
+    /// Interrupt a timer with a message explaining why
+    ///
+    /// See [`Print<state::Background>::cancel`] for details.
+    pub async fn cancel(self, why_details: impl AsRef<str>) -> Print<state::SubBullet<W>> {
+        let mut io = match self.state.write.stop().await {
+            Ok(io) => io,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        };
+
+        render::render(
+            &mut io,
+            Event::TimerCancel(why_details.as_ref().to_string()),
+        );
+        Print {
+            started: self.started,
+            state: state::SubBullet { write: io },
+        }
+    }
+
+    /// Finalize a timer's output.
+    ///
+    /// Once you're finished with your long running task, awaiting this function
+    /// finalizes the timer's output and transitions back to a [`state::SubBullet`].
+    pub async fn done(self) -> Print<state::SubBullet<W>> {
+        let duration = self.state.started.elapsed();
+        let mut io = match self.state.write.stop().await {
+            Ok(io) => io,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        };
+
+        render::render(&mut io, Event::TimerDone(duration));
         Print {
             started: self.started,
             state: state::SubBullet { write: io },
@@ -525,6 +975,69 @@ where
     }
 }
 
+#[cfg(feature = "tokio")]
+impl<W> Print<state::SubBullet<W>>
+where
+    W: Write + Send + Sync + 'static,
+{
+    /// Output periodic timer updates from a `tokio` task.
+    ///
+    /// Same as [`Print::start_timer`] for async code, must be called from within a `tokio`
+    /// runtime. Transitions to [`state::AsyncBackground`].
+    #[must_use]
+    pub fn start_timer_async(self, s: impl AsRef<str>) -> Print<state::AsyncBackground<W>> {
+        self.start_timer_async_with(s, timer::TimerOptions::default())
+    }
+
+    /// Output periodic timer updates from a `tokio` task with a custom interval, ticks, or
+    /// spinner.
+    ///
+    /// See [`Print::start_timer_with`] and [`timer::TimerOptions`] for details.
+    #[must_use]
+    pub fn start_timer_async_with(
+        self,
+        s: impl AsRef<str>,
+        options: timer::TimerOptions,
+    ) -> Print<state::AsyncBackground<W>> {
+        write::sub_start_timer_async(self.state.write, self.started, s, &options)
+    }
+
+    /// Announce and run a `tokio` command while streaming its output
+    ///
+    /// Stdout and stderr are streamed line by line under the sub-bullet as they're produced and
+    /// captured in the returned output. A non-zero exit status is not an error, check
+    /// [`std::process::Output::status`].
+    ///
+    /// ```no_run
+    /// use bullet_stream::Print;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut output = Print::new(std::io::stderr())
+    ///     .h2("Example Buildpack")
+    ///     .bullet("Streaming");
+    ///
+    /// let result = output
+    ///     .stream_cmd_async(tokio::process::Command::new("echo").arg("hello world"))
+    ///     .await
+    ///     .unwrap();
+    /// assert!(result.status.success());
+    ///
+    /// output.done().done();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command cannot be started or its output cannot be read.
+    pub async fn stream_cmd_async(
+        &mut self,
+        command: &mut tokio::process::Command,
+    ) -> std::io::Result<std::process::Output> {
+        write::sub_stream_cmd_async(&mut self.state.write, command).await
+    }
+}
+
 impl<W> Print<state::SubBullet<W>>
 where
     W: Write + Send + Sync + 'static,
@@ -558,37 +1071,82 @@ where
         self
     }
 
-    /// Stream output to the end user.
-    ///
-    /// The most common use case is to stream the output of a running `std::process::Command` to the
-    /// end user. Streaming lets the end user know that something is happening and provides them with
-    /// the output of the process.
-    ///
-    /// The result of this function is a `Print<state::Stream<W>>` which implements [`std::io::Write`].
-    ///
-    /// If you do not wish the end user to view the output of the process, consider using a `step` instead.
+    /// Emit a sub bullet point only when the verbosity is at least `level`.
     ///
-    /// This function will transition your buildpack output to [`state::Stream`].
+    /// Use [`Verbosity::Debug`] for details that only help when debugging a build, such as cache
+    /// keys or resolved paths. See [`Verbosity`] for an example.
     #[must_use]
-    pub fn start_stream(mut self, s: impl AsRef<str>) -> Print<state::Stream<W>> {
-        write::sub_bullet(&mut self.state.write, s);
-        writeln_now(&mut self.state.write, "");
+    pub fn sub_bullet_at(
+        mut self,
+        level: Verbosity,
+        s: impl AsRef<str>,
+    ) -> Print<state::SubBullet<W>> {
+        write::sub_bullet_at(&mut self.state.write, level, s);
+        self
+    }
+
+    /// Emit a sub bullet point step and transition to [`state::Nested`] to add details under it.
+    ///
+    /// Call `done()` on the nested state to return to this [`state::SubBullet`].
+    #[must_use]
+    pub fn nested(mut self, s: impl AsRef<str>) -> Print<state::Nested<W, state::SubBullet<W>>> {
+        write::sub_bullet(&mut self.state.write, s);
+        self.nested_at(Event::NESTED_BULLET_DEPTH)
+    }
+
+    /// Continue with the same writer at a nesting level
+    fn nested_at<P>(self, depth: usize) -> Print<state::Nested<W, P>> {
+        Print {
+            started: self.started,
+            state: state::Nested {
+                write: self.state.write,
+                depth,
+                parent: std::marker::PhantomData,
+            },
+        }
+    }
+
+    /// Stream output to the end user.
+    ///
+    /// The most common use case is to stream the output of a running `std::process::Command` to the
+    /// end user. Streaming lets the end user know that something is happening and provides them with
+    /// the output of the process.
+    ///
+    /// The result of this function is a `Print<state::Stream<W>>` which implements [`std::io::Write`].
+    ///
+    /// If you do not wish the end user to view the output of the process, consider using a `step` instead.
+    ///
+    /// This function will transition your buildpack output to [`state::Stream`].
+    #[must_use]
+    pub fn start_stream(mut self, s: impl AsRef<str>) -> Print<state::Stream<W>> {
+        render::render(
+            &mut self.state.write,
+            Event::StreamStart(s.as_ref().to_string()),
+        );
 
         Print {
             started: self.started,
             state: state::Stream {
                 started: Instant::now(),
-                write: line_mapped(self.state.write, |mut line| {
-                    // Avoid adding trailing whitespace to the line, if there was none already.
-                    // The `[b'\n']` case is required since `line` includes the trailing newline byte.
-                    if line.is_empty() || line == [b'\n'] {
-                        line
-                    } else {
-                        let mut result: Vec<u8> = CMD_INDENT.into();
-                        result.append(&mut line);
-                        result
-                    }
-                }),
+                write: write::stream_state_writer(self.state.write, None),
+                depth: Event::SUB_BULLET_DEPTH,
+                parent: std::marker::PhantomData,
+            },
+        }
+    }
+
+    /// Output progress towards a known total to the end user, such as bytes downloaded.
+    ///
+    /// Call [`Print::inc`] as work completes. This function will transition your buildpack output
+    /// to [`state::Progress`].
+    #[must_use]
+    pub fn start_progress(mut self, s: impl AsRef<str>, total: u64) -> Print<state::Progress<W>> {
+        let bar = progress::ProgressBar::start(&mut self.state.write, s, total);
+        Print {
+            started: self.started,
+            state: state::Progress {
+                write: self.state.write,
+                bar,
             },
         }
     }
@@ -607,7 +1165,61 @@ where
     #[must_use]
     #[allow(unused_mut)]
     pub fn start_timer(mut self, s: impl AsRef<str>) -> Print<state::Background<W>> {
-        write::sub_start_timer(self.state.write, Instant::now(), s)
+        write::sub_start_timer(
+            self.state.write,
+            Instant::now(),
+            s,
+            &timer::TimerOptions::default(),
+        )
+    }
+
+    /// Output periodic timer updates with a custom interval, ticks, or spinner.
+    ///
+    /// Spinners and the elapsed time are redrawn in place on a terminal. See
+    /// [`timer::TimerOptions`] for details.
+    ///
+    /// ```
+    /// use bullet_stream::{timer::TimerOptions, Print};
+    /// # use pretty_assertions::assert_eq;
+    ///
+    /// let output = Print::new(Vec::new())
+    ///     .without_header()
+    ///     .bullet("Ruby")
+    ///     .start_timer_with("Installing", TimerOptions::default().ticks(" [", "#", "] "))
+    ///     .done()
+    ///     .done()
+    ///     .done();
+    ///
+    /// assert_eq!(
+    ///     "- Ruby\n  - Installing [#] (< 0.1s)\n- Done (finished in < 0.1s)\n",
+    ///     bullet_stream::strip_ansi(String::from_utf8_lossy(&output))
+    /// );
+    /// ```
+    #[allow(clippy::missing_panics_doc)]
+    #[must_use]
+    #[allow(unused_mut)]
+    pub fn start_timer_with(
+        mut self,
+        s: impl AsRef<str>,
+        options: timer::TimerOptions,
+    ) -> Print<state::Background<W>> {
+        write::sub_start_timer(self.state.write, Instant::now(), s, &options)
+    }
+
+    /// Run several timed steps at the same time.
+    ///
+    /// On a terminal each step is drawn on its own line and updated in place. Otherwise each step
+    /// is written once it finishes. See the [`parallel`] module for details and an example.
+    ///
+    /// This function will transition your buildpack output to [`state::Parallel`].
+    #[must_use]
+    pub fn start_parallel(self) -> Print<state::Parallel<W>> {
+        Print {
+            started: self.started,
+            state: state::Parallel {
+                group: parallel::Group::start(self.state.write),
+            },
+        }
     }
 
     /// Print command name and run it quietly (don't stream) while emitting timing dots
@@ -633,29 +1245,42 @@ where
     /// output.done().done();
     /// ```
     #[cfg(feature = "fun_run")]
-    #[allow(unused_mut)]
     pub fn time_cmd(
         &mut self,
-        mut command: impl fun_run::CommandWithName,
+        command: impl fun_run::CommandWithName,
     ) -> Result<fun_run::NamedOutput, fun_run::CmdError> {
-        util::mpsc_stream_to_output(
-            |sender| {
-                let start = Instant::now();
-                let background =
-                    write::sub_start_print_interval(sender, style::running_command(command.name()));
-                let output = command.named_output();
-                writeln_now(
-                    &mut background.stop().expect("constructed with valid state"),
-                    style::details(duration_format::human(&start.elapsed())),
-                );
-                output
-            },
-            move |recv| {
-                for message in recv {
-                    self.state.write.write_all(&message).expect("Writeable");
-                }
-            },
-        )
+        write::sub_time_cmd(&mut self.state.write, command)
+    }
+
+    /// Print command name and run it with timing dots, only streaming its output if it fails
+    ///
+    /// Stdout and stderr are captured while the command runs. When it succeeds only the timing
+    /// is printed, like [Self::time_cmd]. When it exits with a non-zero status the captured output
+    /// is printed under the command, like [Self::stream_cmd], followed by the error.
+    ///
+    /// ```
+    /// use bullet_stream::Print;
+    /// use std::process::Command;
+    ///
+    /// let mut output = Print::new(Vec::new())
+    ///     .without_header()
+    ///     .bullet("Installing");
+    ///
+    /// let result = output.time_cmd_stream_on_failure(
+    ///     Command::new("bash").args(["-c", "echo 'no space left on device'; exit 1"]),
+    /// );
+    /// assert!(result.is_err());
+    ///
+    /// let output = bullet_stream::strip_ansi(String::from_utf8_lossy(&output.done().done()));
+    /// assert!(output.contains("\n      no space left on device\n"));
+    /// assert!(output.contains("! exit status: 1\n"));
+    /// ```
+    #[cfg(feature = "fun_run")]
+    pub fn time_cmd_stream_on_failure(
+        &mut self,
+        command: impl fun_run::CommandWithName,
+    ) -> Result<fun_run::NamedOutput, fun_run::CmdError> {
+        write::sub_time_cmd_stream_on_failure(&mut self.state.write, command)
     }
 
     /// Stream two inputs without consuming
@@ -694,7 +1319,47 @@ where
         F: FnMut(Box<dyn Write + Send + Sync>, Box<dyn Write + Send + Sync>) -> T,
         T: 'static,
     {
-        write::sub_stream_with(&mut self.state.write, s, f)
+        write::sub_stream_with(&mut self.state.write, s, None, f, |_| false)
+    }
+
+    /// Stream two inputs like [Self::stream_with], prefixing each line with a timestamp
+    ///
+    /// Elapsed time is measured from the start of the stream. See [`Timestamps`].
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use bullet_stream::{style, Print, Timestamps};
+    /// use fun_run::CommandWithName;
+    /// use std::process::Command;
+    ///
+    /// let mut output = Print::new(std::io::stdout())
+    ///     .h2("Example Buildpack")
+    ///     .bullet("Streaming");
+    ///
+    /// let mut cmd = Command::new("bundle");
+    /// cmd.arg("install");
+    ///
+    /// let result = output.stream_with_timestamps(
+    ///     format!("Running {}", style::command(cmd.name())),
+    ///     Timestamps::Elapsed,
+    ///     |stdout, stderr| cmd.stream_output(stdout, stderr),
+    /// );
+    ///
+    /// output.done().done();
+    /// ```
+    #[allow(clippy::missing_panics_doc)]
+    pub fn stream_with_timestamps<F, T>(
+        &mut self,
+        s: impl AsRef<str>,
+        timestamps: Timestamps,
+        f: F,
+    ) -> T
+    where
+        F: FnMut(Box<dyn Write + Send + Sync>, Box<dyn Write + Send + Sync>) -> T,
+        T: 'static,
+    {
+        write::sub_stream_with(&mut self.state.write, s, Some(timestamps), f, |_| false)
     }
 
     /// Announce and run a command while streaming its output
@@ -728,6 +1393,127 @@ where
         write::sub_stream_cmd(&mut self.state.write, command)
     }
 
+    /// Announce and run a command while streaming only the last `lines` lines of its output
+    ///
+    /// On a terminal the last lines are redrawn in place under the sub-bullet as the command runs,
+    /// up to ten times a second. Otherwise they're written once the command finishes, after a
+    /// count of the lines that were omitted. The full output is captured in the returned value, and every line is sent to
+    /// event sinks, see [`Print::with_events`].
+    ///
+    /// ```
+    /// use bullet_stream::Print;
+    /// use std::process::Command;
+    ///
+    /// let mut output = Print::new(Vec::new())
+    ///     .without_header()
+    ///     .bullet("Compiling");
+    ///
+    /// let result = output
+    ///     .stream_cmd_tail(Command::new("seq").args(["1", "1000"]), 2)
+    ///     .unwrap();
+    /// assert!(result.stdout_lossy().starts_with("1\n2\n3\n"));
+    ///
+    /// let output = bullet_stream::strip_ansi(String::from_utf8_lossy(&output.done().done()));
+    /// assert!(output.contains("\n      … 998 lines omitted …\n      999\n      1000\n"));
+    /// ```
+    #[cfg(feature = "fun_run")]
+    pub fn stream_cmd_tail(
+        &mut self,
+        command: impl fun_run::CommandWithName,
+        lines: usize,
+    ) -> Result<fun_run::NamedOutput, fun_run::CmdError> {
+        write::sub_stream_cmd_tail(&mut self.state.write, command, lines)
+    }
+
+    /// Stream several sources at the same time without consuming
+    ///
+    /// Like [Self::stream_with] but each closure runs on its own thread. Every line is prefixed
+    /// with the label of its source so interleaved output can be told apart, and lines are always
+    /// written whole. The done line reports how long each source took. Results are returned in
+    /// the order the sources were given. See [`parallel::Labelled`].
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use bullet_stream::{parallel::Labelled, Print};
+    /// use std::io::Write;
+    ///
+    /// let mut output = Print::new(Vec::new())
+    ///     .without_header()
+    ///     .bullet("Installing");
+    ///
+    /// let install = |packages: usize| {
+    ///     move |mut stdout: Box<dyn Write + Send + Sync>, _stderr| {
+    ///         writeln!(stdout, "added {packages} packages").map(|_| packages)
+    ///     }
+    /// };
+    /// let results = output.stream_many_with(
+    ///     "Running npm install",
+    ///     vec![
+    ///         Labelled::new("web", install(12)),
+    ///         Labelled::new("api", install(3)).color("1;35"),
+    ///     ],
+    /// );
+    /// assert_eq!(15, results.into_iter().map(Result::unwrap).sum::<usize>());
+    ///
+    /// let output = bullet_stream::strip_ansi(String::from_utf8_lossy(&output.done().done()));
+    /// assert!(output.contains("\n      web | added 12 packages\n"));
+    /// assert!(output.contains("\n      api | added 3 packages\n"));
+    /// assert!(output.contains("  - Done (web in < 0.1s, api in < 0.1s)\n"));
+    /// ```
+    #[allow(clippy::missing_panics_doc)]
+    pub fn stream_many_with<F, T>(
+        &mut self,
+        s: impl AsRef<str>,
+        streams: Vec<parallel::Labelled<F>>,
+    ) -> Vec<T>
+    where
+        F: FnOnce(Box<dyn Write + Send + Sync>, Box<dyn Write + Send + Sync>) -> T + Send,
+        T: Send + 'static,
+    {
+        write::sub_stream_many_with(&mut self.state.write, s, streams, |_| None, |_| false)
+    }
+
+    /// Announce and run several commands at the same time while streaming their output
+    ///
+    /// Each line is prefixed with the label of its command, see [Self::stream_many_with]. The
+    /// done line reports the exit status and duration of every command. Results are returned in
+    /// the order the commands were given.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use bullet_stream::{parallel::Labelled, Print};
+    /// use std::process::Command;
+    ///
+    /// let mut output = Print::new(std::io::stdout())
+    ///     .h2("Example Buildpack")
+    ///     .bullet("Installing");
+    ///
+    /// let mut web = Command::new("npm");
+    /// web.args(["install", "--workspace", "web"]);
+    /// let mut api = Command::new("npm");
+    /// api.args(["install", "--workspace", "api"]);
+    ///
+    /// let results = output.stream_cmds(
+    ///     "Running npm install",
+    ///     vec![Labelled::new("web", web), Labelled::new("api", api).color("1;35")],
+    /// );
+    ///
+    /// output.done().done();
+    /// ```
+    #[cfg(feature = "fun_run")]
+    pub fn stream_cmds<C>(
+        &mut self,
+        s: impl AsRef<str>,
+        commands: Vec<parallel::Labelled<C>>,
+    ) -> Vec<Result<fun_run::NamedOutput, fun_run::CmdError>>
+    where
+        C: fun_run::CommandWithName + Send,
+    {
+        write::sub_stream_cmds(&mut self.state.write, s, commands)
+    }
+
     #[doc = include_str!("docs/stateful_error.md")]
     pub fn error(mut self, s: impl AsRef<str>) -> W {
         write::error(&mut self.state.write, s);
@@ -750,7 +1536,8 @@ where
 
     /// Finish a section and transition back to [`state::Bullet`].
     #[must_use]
-    pub fn done(self) -> Print<state::Bullet<W>> {
+    pub fn done(mut self) -> Print<state::Bullet<W>> {
+        write::bullet_done(&mut self.state.write);
         Print {
             started: self.started,
             state: state::Bullet {
@@ -760,6 +1547,175 @@ where
     }
 }
 
+impl<W, P> Print<state::Nested<W, P>>
+where
+    W: Write + Send + Sync + 'static,
+{
+    /// Emit a bullet point at the current nesting level.
+    ///
+    /// Multi-line text is indented to line up with the first line, the same as [`Print::sub_bullet`].
+    #[must_use]
+    pub fn sub_bullet(mut self, s: impl AsRef<str>) -> Self {
+        write::nested_bullet(&mut self.state.write, self.state.depth, s);
+        self
+    }
+
+    /// Emit a bullet point at the current nesting level and transition one level deeper to add
+    /// details under it.
+    #[must_use]
+    pub fn nested(mut self, s: impl AsRef<str>) -> Print<state::Nested<W, state::Nested<W, P>>> {
+        write::nested_bullet(&mut self.state.write, self.state.depth, s);
+        Print {
+            started: self.started,
+            state: state::Nested {
+                write: self.state.write,
+                depth: self.state.depth + 1,
+                parent: std::marker::PhantomData,
+            },
+        }
+    }
+
+    /// Stream output to the end user, see [`Print<state::SubBullet>::start_stream`].
+    ///
+    /// Call `done()` on the stream to return to this nesting level.
+    #[must_use]
+    pub fn start_stream(self, s: impl AsRef<str>) -> Print<state::Stream<W, state::Nested<W, P>>> {
+        let depth = self.state.depth;
+        self.sub_bullets().start_stream(s).reparent(depth)
+    }
+
+    /// Output periodic timer updates to the end user, see [`Print<state::SubBullet>::start_timer`].
+    ///
+    /// Call `done()` or `cancel()` on the timer to return to this nesting level.
+    ///
+    /// ```
+    /// use bullet_stream::Print;
+    ///
+    /// let output = Print::new(Vec::new())
+    ///     .without_header()
+    ///     .bullet("Dependencies")
+    ///     .nested("Installing packages")
+    ///     .sub_bullet("rake")
+    ///     .start_timer("Compiling")
+    ///     .done()
+    ///     .done()
+    ///     .done()
+    ///     .done();
+    ///
+    /// assert_eq!(
+    ///     "- Dependencies\n  - Installing packages\n    - rake\n  - Compiling ... (< 0.1s)\n- Done (finished in < 0.1s)\n",
+    ///     bullet_stream::strip_ansi(String::from_utf8_lossy(&output))
+    /// );
+    /// ```
+    #[must_use]
+    pub fn start_timer(
+        self,
+        s: impl AsRef<str>,
+    ) -> Print<state::Background<W, state::Nested<W, P>>> {
+        self.start_timer_with(s, timer::TimerOptions::default())
+    }
+
+    /// Output periodic timer updates with a custom interval, ticks, or spinner, see
+    /// [`Print<state::SubBullet>::start_timer_with`].
+    #[must_use]
+    pub fn start_timer_with(
+        self,
+        s: impl AsRef<str>,
+        options: timer::TimerOptions,
+    ) -> Print<state::Background<W, state::Nested<W, P>>> {
+        let depth = self.state.depth;
+        self.sub_bullets()
+            .start_timer_with(s, options)
+            .reparent(depth)
+    }
+
+    /// Continue with the same writer as a [`state::SubBullet`] to share its transitions
+    fn sub_bullets(self) -> Print<state::SubBullet<W>> {
+        Print {
+            started: self.started,
+            state: state::SubBullet {
+                write: self.state.write,
+            },
+        }
+    }
+}
+
+impl<W> Print<state::Nested<W, state::SubBullet<W>>>
+where
+    W: Write + Send + Sync + 'static,
+{
+    /// Finish the nested section and transition back to [`state::SubBullet`].
+    #[must_use]
+    pub fn done(self) -> Print<state::SubBullet<W>> {
+        Print {
+            started: self.started,
+            state: state::SubBullet {
+                write: self.state.write,
+            },
+        }
+    }
+}
+
+impl<W, P> Print<state::Nested<W, state::Nested<W, P>>>
+where
+    W: Write + Send + Sync + 'static,
+{
+    /// Finish the nested section and transition back one level.
+    #[must_use]
+    pub fn done(self) -> Print<state::Nested<W, P>> {
+        Print {
+            started: self.started,
+            state: state::Nested {
+                write: self.state.write,
+                depth: self.state.depth - 1,
+                parent: std::marker::PhantomData,
+            },
+        }
+    }
+}
+
+impl<W> Print<state::Progress<W>>
+where
+    W: Write + Send + Sync + 'static,
+{
+    /// Add to the amount of progress made, such as the number of bytes downloaded.
+    pub fn inc(&mut self, n: u64) {
+        self.state.bar.inc(&mut self.state.write, n);
+    }
+
+    /// Report bytes read from `read` as progress, see the [`progress`] module for an example.
+    pub fn reader<R: std::io::Read>(&mut self, read: R) -> progress::ProgressRead<'_, R> {
+        progress::ProgressRead::new(read, self)
+    }
+
+    /// Report bytes written to `write` as progress, see the [`progress`] module.
+    pub fn writer<T: Write>(&mut self, write: T) -> progress::ProgressWrite<'_, T> {
+        progress::ProgressWrite::new(write, self)
+    }
+
+    /// Finalize the progress output with the elapsed time and transition back to a
+    /// [`state::SubBullet`].
+    #[must_use]
+    pub fn done(mut self) -> Print<state::SubBullet<W>> {
+        self.state.bar.done(&mut self.state.write);
+        Print {
+            started: self.started,
+            state: state::SubBullet {
+                write: self.state.write,
+            },
+        }
+    }
+}
+
+impl<W> progress::Progressed for Print<state::Progress<W>>
+where
+    W: Write + Send + Sync + 'static,
+{
+    fn inc(&mut self, n: u64) {
+        Print::inc(self, n);
+    }
+}
+
 impl<W> Print<state::Stream<W>>
 where
     W: Write + Send + Sync + 'static,
@@ -775,22 +1731,70 @@ where
         let mut output = Print {
             started: self.started,
             state: state::SubBullet {
-                write: self.state.write.unwrap(),
+                write: self.state.write.unwrap().unwrap(),
             },
         };
 
-        if !output.state.write.was_paragraph {
-            writeln_now(&mut output.state.write, "");
+        render::render(&mut output.state.write, Event::StreamDone(duration));
+        output
+    }
+}
+
+impl<W, P> Print<state::Stream<W, state::Nested<W, P>>>
+where
+    W: Write + Send + Sync + 'static,
+{
+    /// Finalize a stream's output and transition back to [`state::Nested`].
+    #[must_use]
+    pub fn done(self) -> Print<state::Nested<W, P>> {
+        let depth = self.state.depth;
+        self.reparent::<state::SubBullet<W>>(Event::SUB_BULLET_DEPTH)
+            .done()
+            .nested_at(depth)
+    }
+}
+
+impl<W, P> Print<state::Stream<W, P>>
+where
+    W: Write + Send + Sync + 'static,
+{
+    /// Return to `Q` at `depth` once the stream is finished
+    fn reparent<Q>(self, depth: usize) -> Print<state::Stream<W, Q>> {
+        Print {
+            started: self.started,
+            state: state::Stream {
+                started: self.state.started,
+                write: self.state.write,
+                depth,
+                parent: std::marker::PhantomData,
+            },
         }
+    }
 
-        output.sub_bullet(format!(
-            "Done {}",
-            style::details(duration_format::human(&duration))
-        ))
+    /// Prefix each line written from now on with a timestamp, see [`Timestamps`]
+    ///
+    /// Elapsed time is measured from the start of the stream.
+    #[must_use]
+    pub fn with_timestamps(self, timestamps: Timestamps) -> Self {
+        let write = self.state.write.unwrap().unwrap();
+        let stamp = timestamp::Stamp::new(
+            timestamps,
+            self.state.started,
+            write.config.theme.dim.clone(),
+        );
+        Print {
+            started: self.started,
+            state: state::Stream {
+                started: self.state.started,
+                write: write::stream_state_writer(write, Some(stamp)),
+                depth: self.state.depth,
+                parent: std::marker::PhantomData,
+            },
+        }
     }
 }
 
-impl<W> Write for Print<state::Stream<W>>
+impl<W, P> Write for Print<state::Stream<W, P>>
 where
     W: Write,
 {
@@ -803,13 +1807,6 @@ where
     }
 }
 
-/// Internal helper, ensures that all contents are always flushed (never buffered).
-fn writeln_now<D: Write>(destination: &mut D, msg: impl AsRef<str>) {
-    writeln!(destination, "{}", msg.as_ref()).expect("Output error: UI writer closed");
-
-    destination.flush().expect("Output error: UI writer closed");
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
@@ -818,6 +1815,7 @@ mod test {
     use fun_run::CommandWithName;
     use indoc::formatdoc;
     use libcnb_test::assert_contains;
+    use parallel::Labelled;
     use pretty_assertions::assert_eq;
     use std::{fs::File, process::Command};
 
@@ -844,6 +1842,80 @@ mod test {
         assert_eq!(expected, strip_ansi(String::from_utf8_lossy(&io)))
     }
 
+    #[test]
+    fn nested_bullets() {
+        let io = Print::new(Vec::new())
+            .without_header()
+            .bullet("Bullet")
+            .nested("Level 2")
+            .nested("Level 3\nsecond line")
+            .nested("Level 4")
+            .sub_bullet("Level 5")
+            .done()
+            .sub_bullet("Also level 4")
+            .done()
+            .sub_bullet("Back to level 3")
+            .done()
+            .sub_bullet("Back to level 2")
+            .done()
+            .done();
+
+        let expected = formatdoc! {"
+            - Bullet
+              - Level 2
+                - Level 3
+                  second line
+                  - Level 4
+                    - Level 5
+                  - Also level 4
+                - Back to level 3
+              - Back to level 2
+            - Done (finished in < 0.1s)
+        "};
+
+        assert_eq!(expected, strip_ansi(String::from_utf8_lossy(&io)))
+    }
+
+    #[test]
+    fn nested_timers_and_streams_return_to_the_same_level() {
+        let mut stream = Print::new(Vec::new())
+            .without_header()
+            .bullet("Bullet")
+            .nested("Level 2")
+            .nested("Level 3")
+            .start_timer("Timer")
+            .cancel("Interrupted")
+            .sub_bullet("Still level 4")
+            .start_stream("Streaming");
+        writeln!(stream, "hello").unwrap();
+        let io = stream
+            .done()
+            .sub_bullet("Also level 4")
+            .done()
+            .sub_bullet("Back to level 3")
+            .done()
+            .done()
+            .done();
+
+        let expected = formatdoc! {"
+            - Bullet
+              - Level 2
+                - Level 3
+              - Timer ... (Interrupted)
+                  - Still level 4
+              - Streaming
+
+                  hello
+
+              - Done (< 0.1s)
+                  - Also level 4
+                - Back to level 3
+            - Done (finished in < 0.1s)
+        "};
+
+        assert_eq!(expected, strip_ansi(String::from_utf8_lossy(&io)))
+    }
+
     #[test]
     fn double_h1_h2_newlines() {
         let writer = Vec::new();
@@ -909,9 +1981,41 @@ mod test {
         assert_eq!(expected, strip_ansi(String::from_utf8_lossy(&io)));
     }
 
+    #[test]
+    fn stream_with_timestamps() {
+        let mut output = Print::new(Vec::new()).without_header().bullet("Streaming");
+        output.stream_with_timestamps("Running", Timestamps::Elapsed, |mut stdout, _| {
+            write!(stdout, "hello\n\nworld\n").unwrap();
+        });
+
+        let io = strip_ansi(String::from_utf8_lossy(&output.done().done()));
+        let lines = io.lines().collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "- Streaming",
+                "  - Running",
+                "",
+                "      hello",
+                "",
+                "      world",
+                ""
+            ],
+            lines[..7]
+                .iter()
+                .map(|line| line
+                    .trim_start_matches(|c: char| c.is_ascii_digit() || c == ':' || c == '.'))
+                .collect::<Vec<_>>(),
+            "{io}"
+        );
+        assert!(lines[3].starts_with("00:00.0"), "{io}");
+        assert!(lines[5].starts_with("00:00.0"), "{io}");
+        assert_eq!("  - Done (< 0.1s)", lines[7]);
+    }
+
     #[test]
     fn background_timer() {
         let io = Print::new(Vec::new())
+            .with_color(ColorChoice::Always)
             .without_header()
             .bullet("Background")
             .start_timer("Installing")
@@ -988,6 +2092,7 @@ mod test {
     #[test]
     fn paragraph_color_codes() {
         let io = Print::new(Vec::new())
+            .with_color(ColorChoice::Always)
             .h1("Buildpack Header is Bold Purple")
             .important("Important is bold cyan")
             .warning("Warnings are yellow")
@@ -1008,6 +2113,171 @@ mod test {
         assert_eq!(expected, String::from_utf8_lossy(&io));
     }
 
+    #[test]
+    fn no_color_matches_strip_ansi() {
+        let build = |choice| {
+            Print::new(Vec::new())
+                .with_color(choice)
+                .h1("Buildpack")
+                .important(format!("Visit {}", style::url("https://example.com")))
+                .bullet(format!("Using {}", style::value("3.4.1")))
+                .start_timer("Installing")
+                .done()
+                .done()
+                .warning("Careful")
+                .done()
+        };
+        let colored = String::from_utf8(build(ColorChoice::Always)).unwrap();
+        let plain = String::from_utf8(build(ColorChoice::Never)).unwrap();
+
+        assert_contains!(colored, "\x1B[");
+        assert_eq!(strip_ansi(colored), plain);
+    }
+
+    #[test]
+    fn theme_colors() {
+        let io = Print::new(Vec::new())
+            .with_color(ColorChoice::Always)
+            .with_theme(
+                style::Theme::default()
+                    .h1("1;34")
+                    .h3("36")
+                    .important("")
+                    .warning("1;33")
+                    .dim("90"),
+            )
+            .h1("Blue header")
+            .h3("Cyan header")
+            .important("Plain important")
+            .warning("Bold yellow warning")
+            .bullet("Background")
+            .start_timer("Installing")
+            .done()
+            .done()
+            .done();
+
+        let expected = formatdoc! {"
+
+            \u{1b}[1;34m# Blue header\u{1b}[0m
+
+            \u{1b}[36m### Cyan header\u{1b}[0m
+
+            ! Plain important
+
+            \u{1b}[1;33m! Bold yellow warning\u{1b}[0m
+
+            - Background
+              - Installing\u{1b}[90m .\u{1b}[0m\u{1b}[90m.\u{1b}[0m\u{1b}[90m. \u{1b}[0m(< 0.1s)
+            - Done (finished in < 0.1s)
+        "};
+
+        assert_eq!(expected, String::from_utf8_lossy(&io));
+    }
+
+    #[test]
+    fn stream_on_failure_events() {
+        #[derive(Clone, Default)]
+        struct Collect(std::sync::Arc<std::sync::Mutex<Vec<Event>>>);
+        impl event::EventSink for Collect {
+            fn event(&mut self, _at: std::time::SystemTime, event: &Event) {
+                self.0.lock().unwrap().push(event.clone());
+            }
+        }
+
+        let events = Collect::default();
+        let mut bullet = Print::new(Vec::new())
+            .with_events(events.clone())
+            .without_header()
+            .bullet("Ruby");
+        bullet
+            .time_cmd_stream_on_failure(Command::new("bash").args(["-c", "echo oops; exit 1"]))
+            .unwrap_err();
+        bullet.done().done();
+
+        let names = events
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|event| event.name())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "bullet",
+                "timer_start",
+                "timer_done",
+                "stream_start",
+                "stream_output",
+                "stream_done",
+                "error",
+                "bullet_done",
+                "all_done"
+            ],
+            names
+        );
+    }
+
+    #[test]
+    fn emits_events() {
+        #[derive(Clone, Default)]
+        struct Collect(std::sync::Arc<std::sync::Mutex<Vec<Event>>>);
+        impl event::EventSink for Collect {
+            fn event(&mut self, _at: std::time::SystemTime, event: &Event) {
+                self.0.lock().unwrap().push(event.clone());
+            }
+        }
+
+        let events = Collect::default();
+        let mut stream = Print::new(Vec::new())
+            .with_events(events.clone())
+            .h1("Buildpack")
+            .warning("Careful")
+            .bullet("Ruby")
+            .sub_bullet("Using 3.4.1")
+            .start_stream("Streaming");
+        writeln!(stream, "hello").unwrap();
+        let io = stream
+            .done()
+            .start_timer("Installing")
+            .cancel("Skipped")
+            .start_timer("Installing")
+            .done()
+            .done()
+            .done();
+
+        let names = events
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|event| format!("{}:{}", event.depth(), event.name()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "0:h1",
+                "0:warning",
+                "1:bullet",
+                "2:sub_bullet",
+                "2:stream_start",
+                "3:stream_output",
+                "2:stream_done",
+                "2:timer_start",
+                "2:timer_cancel",
+                "2:timer_start",
+                "2:timer_done",
+                "1:bullet_done",
+                "1:all_done"
+            ],
+            names
+        );
+
+        // Output is not changed
+        assert_contains!(
+            strip_ansi(String::from_utf8_lossy(&io)),
+            "  - Installing ... (Skipped)\n"
+        );
+    }
+
     #[test]
     fn test_important() {
         let writer = Vec::new();
@@ -1276,4 +2546,187 @@ mod test {
         "};
         assert_eq!(expected, strip_ansi(String::from_utf8_lossy(&io)));
     }
+
+    #[test]
+    fn test_stream_cmds() {
+        let mut web = Command::new("bash");
+        web.args(["-c", "echo compiled; echo warned >&2"]);
+        let mut api = Command::new("bash");
+        api.args(["-c", "sleep 0.02; echo failed; exit 3"]);
+
+        let mut bullet = Print::new(Vec::new()).without_header().bullet("Installing");
+        let results = bullet.stream_cmds(
+            "Running npm install",
+            vec![
+                Labelled::new("web", web),
+                Labelled::new("api-server", api).color("1;35"),
+            ],
+        );
+        assert!(results[0].is_ok());
+        assert_eq!(Some(3), results[1].as_ref().unwrap_err().status().code());
+
+        let io = strip_ansi(String::from_utf8_lossy(&bullet.done().done()));
+        assert!(
+            io.starts_with("- Installing\n  - Running npm install\n\n"),
+            "{io}"
+        );
+        assert!(io.contains("\n      web        | compiled\n"), "{io}");
+        assert!(io.contains("\n      web        | warned\n"), "{io}");
+        assert!(
+            io.ends_with(&formatdoc! {"
+                      api-server | failed
+
+                  - Done (web exit status 0 in < 0.1s, api-server exit status 3 in < 0.1s)
+                - Done (finished in < 0.1s)
+            "}),
+            "{io}"
+        );
+    }
+
+    #[test]
+    fn line_safe_streams() {
+        let mut bullet = Print::new(Vec::new())
+            .with_color(ColorChoice::Always)
+            .with_line_safe(true)
+            .without_header()
+            .bullet("Assets");
+        bullet.stream_many_with(
+            "Compiling",
+            vec![Labelled::new(
+                "css",
+                |mut stdout: Box<dyn Write + Send + Sync>, _| {
+                    write!(stdout, "\x1B[32m10%\r\x1B[K50%\r100%\x1B[0m\n").unwrap();
+                },
+            )],
+        );
+        bullet.stream_with("Uploading", |mut stdout, _| {
+            write!(stdout, "1/2\r2/2\r\n").unwrap();
+        });
+
+        let io = String::from_utf8_lossy(&bullet.done().done()).into_owned();
+        assert!(
+            io.contains("      css | \x1B[32m10%\x1B[0m\n      css | \x1B[32m50%\x1B[0m\n      css | \x1B[32m100%\x1B[0m\n"),
+            "{io:?}"
+        );
+        assert!(io.contains("      1/2\n      2/2\n\n"), "{io:?}");
+    }
+
+    #[test]
+    fn quiet_streams_show_output_of_failures() {
+        let mut bullet = Print::new(Vec::new())
+            .with_verbosity(Verbosity::Quiet)
+            .without_header()
+            .bullet("Installing");
+        bullet
+            .stream_cmd(Command::new("bash").args(["-c", "echo installed"]))
+            .unwrap();
+        bullet
+            .stream_cmd(Command::new("bash").args(["-c", "echo broken >&2; exit 1"]))
+            .unwrap_err();
+        bullet.stream_with("Streaming", |mut stdout, _| {
+            writeln!(stdout, "hidden").unwrap();
+        });
+
+        let io = strip_ansi(String::from_utf8_lossy(&bullet.done().done()));
+        assert!(!io.contains("      installed\n"), "{io}");
+        assert!(!io.contains("hidden"), "{io}");
+        assert!(io.contains("\n      broken\n"), "{io}");
+    }
+
+    #[test]
+    fn redraws_collapse_before_indenting() {
+        let mut bullet = Print::new(Vec::new())
+            .with_redraws(Redraws::Throttle(std::time::Duration::ZERO))
+            .without_header()
+            .bullet("Downloading");
+        bullet.stream_with("Fetching", |mut stdout, _| {
+            write!(stdout, "10%\r50%\r\r100%\r\nsaved").unwrap();
+        });
+
+        let io = strip_ansi(String::from_utf8_lossy(&bullet.done().done()));
+        assert!(
+            io.contains("\n      10%\n      50%\n      100%\r\n      saved\n"),
+            "{io:?}"
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_cmd_and_timer() {
+        let mut bullet = Print::new(Vec::new()).without_header().bullet("Async");
+
+        let output = bullet
+            .stream_cmd_async(
+                tokio::process::Command::new("bash")
+                    .arg("-c")
+                    .arg("echo it would be awesome; sleep 0.02; echo if we could dance >&2"),
+            )
+            .await
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            "it would be awesome\n",
+            String::from_utf8_lossy(&output.stdout)
+        );
+        assert_eq!(
+            "if we could dance\n",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let timer = bullet.start_timer_async("Waiting");
+        tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        let bullet = timer.done().await;
+        let io = bullet
+            .start_timer_async("Retrying")
+            .cancel("Interrupted")
+            .await
+            .done()
+            .done();
+
+        let expected = formatdoc! {"
+            - Async
+              - Running `bash -c \"echo it would be awesome; sleep 0.02; echo if we could dance >&2\"`
+
+                  it would be awesome
+                  if we could dance
+
+              - Done (< 0.1s)
+              - Waiting ... (< 0.1s)
+              - Retrying ... (Interrupted)
+            - Done (finished in < 0.1s)
+        "};
+        assert_eq!(expected, strip_ansi(String::from_utf8_lossy(&io)));
+    }
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_cmd_that_cannot_start() {
+        let mut bullet = Print::new(Vec::new()).without_header().bullet("Async");
+        let result = bullet
+            .stream_cmd_async(&mut tokio::process::Command::new("iDoNotExist"))
+            .await;
+        assert!(result.is_err());
+
+        let io = bullet.sub_bullet("After").done().done();
+        assert_eq!(
+            "- Async\n  - After\n- Done (finished in < 0.1s)\n",
+            strip_ansi(String::from_utf8_lossy(&io))
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_timer_zero_interval() {
+        let io = Print::new(Vec::new())
+            .without_header()
+            .bullet("Async")
+            .start_timer_async_with(
+                "Waiting",
+                timer::TimerOptions::default().interval(std::time::Duration::ZERO),
+            )
+            .done()
+            .await
+            .done()
+            .done();
+        assert!(strip_ansi(String::from_utf8_lossy(&io)).contains("  - Waiting ."));
+    }
 }
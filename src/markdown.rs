@@ -0,0 +1,407 @@
+//! Export output as a Markdown report
+//!
+//! The [`MarkdownReport`] event sink writes a [CommonMark](https://commonmark.org/) document
+//! alongside the regular output. It's suitable for attaching to CI job summaries or pull request
+//! comments:
+//!
+//! - Headers become headings
+//! - Bullets and sub bullets become nested lists
+//! - Streamed output becomes a fenced code block
+//! - Warnings, errors, and important messages become blockquotes using
+//!   [alert syntax](https://github.com/orgs/community/discussions/16925)
+//! - Text styled with [`crate::style::value`] or [`crate::style::command`] becomes a code span,
+//!   and [`crate::style::url`] becomes a link
+//! - Other text is escaped, so characters such as `*` or `#` don't change the structure
+//!
+//! ```
+//! use bullet_stream::{markdown::MarkdownReport, style, Print};
+//! use std::io::Write;
+//!
+//! let file = tempfile::NamedTempFile::new().unwrap();
+//! let mut stream = Print::new(std::io::sink())
+//!     .with_events(MarkdownReport::new(file.reopen().unwrap()))
+//!     .h2("Example Buildpack")
+//!     .bullet("Ruby version")
+//!     .sub_bullet(format!("Using {}", style::value("3.4.1")))
+//!     .start_stream("Installing");
+//! writeln!(stream, "Installed!").unwrap();
+//! stream.done().done().warning(format!("See {}", style::url("https://example.com")));
+//!
+//! let markdown = std::fs::read_to_string(file.path()).unwrap();
+//! assert!(markdown.starts_with(
+//!     "## Example Buildpack\n\n- Ruby version\n  - Using `3.4.1`\n  - Installing\n    ```\n    Installed!\n    ```\n"
+//! ));
+//! assert!(markdown.ends_with("\n> [!WARNING]\n> See <https://example.com>\n\n"));
+//! ```
+use crate::ansi_escape::strip_ansi;
+use crate::duration_format;
+use crate::event::{Event, EventSink};
+use crate::style::Theme;
+use std::io::Write;
+use std::time::SystemTime;
+
+const RESET: &str = "\x1B[0m";
+/// Characters that are escaped with a backslash wherever they appear in text
+const ESCAPED: &str = "\\`*_[]<>#~|&";
+const SUB_INDENT: &str = "  ";
+const STREAM_INDENT: &str = "    ";
+
+/// Writes events as a Markdown document.
+///
+/// The document is written as events arrive, except for streamed output which is written
+/// once the stream is done so that the code fence can be longer than any backticks in the output.
+#[derive(Debug)]
+pub struct MarkdownReport<W> {
+    write: W,
+    url: String,
+    in_list: bool,
+    stream: Option<Vec<String>>,
+    timer: Option<String>,
+}
+
+impl<W: Write> MarkdownReport<W> {
+    pub fn new(write: W) -> Self {
+        Self {
+            write,
+            url: Theme::default().url.to_str().to_string(),
+            in_list: false,
+            stream: None,
+            timer: None,
+        }
+    }
+
+    /// The theme used to detect text styled with [`crate::style::url`]
+    ///
+    /// Only needed if the theme was changed with [`crate::global::set_theme`].
+    #[must_use]
+    pub fn with_theme(mut self, theme: &Theme) -> Self {
+        self.url = theme.url.to_str().to_string();
+        self
+    }
+
+    /// Return the original writer
+    pub fn into_inner(self) -> W {
+        self.write
+    }
+
+    /// Convert styled text to Markdown, escaping everything that isn't a code span or a link
+    fn text(&self, contents: &str) -> String {
+        let mut result = String::new();
+        let mut rest = contents.trim();
+        loop {
+            // A value is quoted with backticks around its color
+            let code = rest.find("`\x1B[").and_then(|start| {
+                let end = rest[start..].find(&format!("{RESET}`"))? + start;
+                Some((start, start + 1, end + RESET.len(), end + RESET.len() + 1))
+            });
+            let url = (!self.url.is_empty())
+                .then(|| rest.find(&self.url))
+                .flatten()
+                .map(|start| {
+                    let inner = start + self.url.len();
+                    let end = rest[inner..]
+                        .find(RESET)
+                        .map_or(rest.len(), |end| end + inner);
+                    (start, inner, end, (end + RESET.len()).min(rest.len()))
+                });
+            let (start, inner, end, after, is_code) = match (code, url) {
+                (Some(code), Some(url)) if url.0 < code.0 => (url.0, url.1, url.2, url.3, false),
+                (Some(code), _) => (code.0, code.1, code.2, code.3, true),
+                (None, Some(url)) => (url.0, url.1, url.2, url.3, false),
+                (None, None) => break,
+            };
+
+            let line_start = result.is_empty() || result.ends_with('\n');
+            result.push_str(&escape(&strip_ansi(&rest[..start]), line_start));
+            let inner = strip_ansi(&rest[inner..end]);
+            if is_code {
+                result.push_str(&code_span(&inner));
+            } else if inner.contains("://") && !inner.contains(char::is_whitespace) {
+                result.push_str(&format!("<{}>", inner.replace(['<', '>'], "")));
+            } else if inner.contains([' ', '(', ')', '<', '>']) {
+                result.push_str(&escape(&inner, false));
+            } else {
+                result.push_str(&format!("[{}]({inner})", escape(&inner, false)));
+            }
+            rest = &rest[after..];
+        }
+        let line_start = result.is_empty() || result.ends_with('\n');
+        result.push_str(&escape(&strip_ansi(rest), line_start));
+        result
+    }
+
+    fn block(&mut self, contents: &str) {
+        if self.in_list {
+            writeln!(self.write).expect("Markdown writer not to be closed");
+        }
+        writeln!(self.write, "{contents}\n").expect("Markdown writer not to be closed");
+        self.in_list = false;
+    }
+
+    fn item(&mut self, indent: &str, contents: &str) {
+        for (index, line) in contents.lines().enumerate() {
+            let prefix = if index == 0 { "- " } else { "  " };
+            writeln!(self.write, "{indent}{prefix}{line}")
+                .expect("Markdown writer not to be closed");
+        }
+        self.in_list = true;
+    }
+
+    fn alert(&mut self, kind: &str, contents: &str) {
+        let mut quote = format!("> [!{kind}]");
+        for line in contents.lines() {
+            quote.push_str("\n>");
+            if !line.is_empty() {
+                quote.push(' ');
+                quote.push_str(line);
+            }
+        }
+        self.block(&quote);
+    }
+
+    fn stream_done(&mut self, details: &str) {
+        if let Some(lines) = self.stream.take() {
+            if !lines.is_empty() {
+                self.code_block(&lines);
+            }
+        }
+        self.item(SUB_INDENT, &format!("Done ({details})"));
+    }
+
+    fn code_block(&mut self, lines: &[String]) {
+        let longest_backticks = lines
+            .iter()
+            .flat_map(|line| line.split(|c| c != '`'))
+            .map(str::len)
+            .max()
+            .unwrap_or_default();
+        let fence = "`".repeat(longest_backticks.max(2) + 1);
+
+        writeln!(self.write, "{STREAM_INDENT}{fence}").expect("Markdown writer not to be closed");
+        for line in lines {
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                writeln!(self.write).expect("Markdown writer not to be closed");
+            } else {
+                writeln!(self.write, "{STREAM_INDENT}{line}")
+                    .expect("Markdown writer not to be closed");
+            }
+        }
+        writeln!(self.write, "{STREAM_INDENT}{fence}").expect("Markdown writer not to be closed");
+    }
+}
+
+impl<W: Write + Send> EventSink for MarkdownReport<W> {
+    fn event(&mut self, _at: SystemTime, event: &Event) {
+        match event {
+            Event::H1(s) => self.block(&format!("# {}", self.text(s))),
+            Event::H2(s) => self.block(&format!("## {}", self.text(s))),
+            Event::H3(s) => self.block(&format!("### {}", self.text(s))),
+            Event::Plain(s) => {
+                let contents = self.text(s);
+                if !contents.is_empty() {
+                    self.block(&contents);
+                }
+            }
+            Event::Bullet(s) => self.item("", &self.text(s)),
+            Event::NestedBullet { depth, text } => {
+                self.item(
+                    &SUB_INDENT.repeat(depth.saturating_sub(1)),
+                    &self.text(text),
+                );
+            }
+            Event::BulletDone => {}
+            Event::SubBullet(s) => self.item(SUB_INDENT, &self.text(s)),
+            Event::Warning(s) => self.alert("WARNING", &self.text(s)),
+            Event::Error(s) => self.alert("CAUTION", &self.text(s)),
+            Event::Important(s) => self.alert("IMPORTANT", &self.text(s)),
+            Event::StreamStart(s) => {
+                self.item(SUB_INDENT, &self.text(s));
+                self.stream = Some(Vec::new());
+            }
+            Event::StreamOutput(line) => {
+                if let Some(lines) = self.stream.as_mut() {
+                    lines.push(strip_ansi(line));
+                }
+            }
+            Event::StreamDone(duration) => self.stream_done(&duration_format::human(duration)),
+            Event::StreamsDone { summary, .. } => self.stream_done(&self.text(summary)),
+            Event::TimerStart(s) => self.timer = Some(self.text(s)),
+            Event::TimerDone(duration) => {
+                let text = self.timer.take().unwrap_or_default();
+                self.item(
+                    SUB_INDENT,
+                    &format!("{text} ({})", duration_format::human(duration)),
+                );
+            }
+            Event::TimerCancel(why) => {
+                let text = self.timer.take().unwrap_or_default();
+                self.item(SUB_INDENT, &format!("{text} ({})", self.text(why)));
+            }
+            Event::AllDone(Some(duration)) => self.item(
+                "",
+                &format!("Done (finished in {})", duration_format::human(duration)),
+            ),
+            Event::AllDone(None) => self.item("", "Done"),
+        }
+        self.write
+            .flush()
+            .expect("Markdown writer not to be closed");
+    }
+}
+
+/// Escape characters that Markdown would treat as formatting, along with list and heading
+/// markers at the start of a line
+fn escape(text: &str, mut line_start: bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if line_start && c != ' ' {
+            line_start = false;
+            if matches!(c, '-' | '+' | '=') {
+                result.push('\\');
+            } else if c.is_ascii_digit() {
+                result.push(c);
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    result.push(digit);
+                }
+                if let Some(marker) = chars.next_if(|next| matches!(next, '.' | ')')) {
+                    result.push('\\');
+                    result.push(marker);
+                }
+                continue;
+            }
+        }
+        if c == '\n' {
+            line_start = true;
+        } else if ESCAPED.contains(c) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Quote text in a code span with a fence longer than any backticks it contains
+fn code_span(text: &str) -> String {
+    let longest_backticks = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest_backticks + 1);
+    let padding = if text.starts_with('`') || text.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{fence}{padding}{text}{padding}{fence}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::style;
+    use indoc::formatdoc;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    fn report(events: Vec<Event>) -> String {
+        let mut sink = MarkdownReport::new(Vec::new());
+        for event in events {
+            sink.event(SystemTime::now(), &event);
+        }
+        String::from_utf8(sink.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn full_report() {
+        let actual = report(vec![
+            Event::H2("Example Buildpack".to_string()),
+            Event::Bullet("Ruby version".to_string()),
+            Event::SubBullet(format!("Using {}", style::value("3.4.1"))),
+            Event::TimerStart("Downloading".to_string()),
+            Event::TimerDone(Duration::from_millis(1200)),
+            Event::StreamStart(style::running_command("bundle install")),
+            Event::StreamOutput("\x1B[0;32mBundle complete!\x1B[0m\n".to_string()),
+            Event::StreamOutput("\n".to_string()),
+            Event::StreamOutput("Use `bundle info`".to_string()),
+            Event::StreamDone(Duration::from_secs(3)),
+            Event::Error(format!(
+                "Something broke\n\nSee {}",
+                style::url("https://example.com")
+            )),
+            Event::Plain(format!("Docs at {}", style::url("docs/ruby.md"))),
+            Event::Bullet("Next".to_string()),
+            Event::AllDone(None),
+        ]);
+
+        assert_eq!(
+            formatdoc! {"
+                ## Example Buildpack
+
+                - Ruby version
+                  - Using `3.4.1`
+                  - Downloading (1.2s)
+                  - Running `bundle install`
+                    ```
+                    Bundle complete!
+
+                    Use `bundle info`
+                    ```
+                  - Done (3.0s)
+
+                > [!CAUTION]
+                > Something broke
+                >
+                > See <https://example.com>
+
+                Docs at [docs/ruby.md](docs/ruby.md)
+
+                - Next
+                - Done
+            "},
+            actual
+        );
+    }
+
+    #[test]
+    fn fence_longer_than_output_backticks() {
+        let actual = report(vec![
+            Event::StreamStart("Printing".to_string()),
+            Event::StreamOutput("````\n".to_string()),
+            Event::StreamDone(Duration::from_secs(0)),
+        ]);
+
+        assert!(
+            actual.contains("    `````\n    ````\n    `````\n"),
+            "{actual}"
+        );
+    }
+
+    #[test]
+    fn escapes_text() {
+        let actual = report(vec![
+            Event::H2("# Not *a* _heading_".to_string()),
+            Event::Bullet("Install <gem> [1]".to_string()),
+            Event::SubBullet(format!("Using {} with `ticks`", style::value("a`b"))),
+            Event::Plain("- not a list\n1. or this\n+ or = this\n\\ & |".to_string()),
+        ]);
+
+        assert_eq!(
+            formatdoc! {r"
+                ## \# Not \*a\* \_heading\_
+
+                - Install \<gem\> \[1\]
+                  - Using ``a`b`` with \`ticks\`
+
+                \- not a list
+                1\. or this
+                \+ or = this
+                \\ \& \|
+
+            "},
+            actual
+        );
+    }
+}
@@ -0,0 +1,522 @@
+//! Run several timed steps at the same time
+//!
+//! A timer started with [`crate::Print::start_timer`] consumes the output until it's done, so only
+//! one step can run at a time. A parallel group started with [`crate::Print::start_parallel`]
+//! hands out a [`Step`] for each labelled sub-step that can be finished from any thread.
+//!
+//! On a terminal every step gets its own line that is redrawn in place with the time elapsed so
+//! far. Otherwise each step is written as a single line when it finishes, in the order they
+//! finish. Newlines in labels are replaced with spaces to keep each step on one line.
+//!
+//! To stream the output of several commands at once instead, give each a [`Labelled`] prefix and
+//! use [`crate::Print::stream_cmds`] or [`crate::Print::stream_many_with`].
+//!
+//! ```
+//! use bullet_stream::Print;
+//!
+//! let mut group = Print::new(Vec::new())
+//!     .without_header()
+//!     .bullet("Downloading")
+//!     .start_parallel();
+//!
+//! let ruby = group.step("Ruby");
+//! let node = group.step("Node.js");
+//! std::thread::scope(|scope| {
+//!     scope.spawn(move || node.done());
+//! });
+//! ruby.done();
+//!
+//! let output = group.done().done().done();
+//! assert_eq!(
+//!     "- Downloading\n  - Node.js ... (< 0.1s)\n  - Ruby ... (< 0.1s)\n- Done (finished in < 0.1s)\n",
+//!     bullet_stream::strip_ansi(String::from_utf8_lossy(&output))
+//! );
+//! ```
+use crate::ansi_escape::{self, visible_width, ANSI, CLEAR_LINE};
+use crate::duration_format;
+use crate::event::Event;
+use crate::render::{render, Output, TimerTicks};
+use crate::util::{self, ParagraphInspectWrite};
+use crate::ColorChoice;
+use std::fmt::Debug;
+use std::io::Write;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How often running steps are redrawn on a terminal
+const INTERVAL: Duration = Duration::from_secs(1);
+/// Width used to count wrapped rows when the size of the terminal cannot be detected
+const DEFAULT_WIDTH: usize = 80;
+
+/// How a step finished
+#[derive(Debug, Clone)]
+enum Finished {
+    Done(Duration),
+    Cancel(String),
+    /// Dropped without being finished
+    Error,
+}
+
+#[derive(Debug)]
+struct StepState {
+    label: String,
+    started: Instant,
+    finished: Option<Finished>,
+    /// Written to non-terminals
+    written: bool,
+}
+
+/// Writes the steps of a parallel group
+struct Display<W> {
+    write: Option<ParagraphInspectWrite<W>>,
+    steps: Vec<StepState>,
+    ticks: TimerTicks,
+    /// Number of rows drawn on a terminal, a line wider than the terminal takes up several
+    drawn: usize,
+}
+
+impl<W> Debug for Display<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Display")
+            .field("steps", &self.steps)
+            .field("drawn", &self.drawn)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W: Write> Display<W> {
+    fn add(&mut self, label: String) -> usize {
+        // A line that contains a newline can't be redrawn
+        let label = label
+            .split(['\n', '\r'])
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        // Labels are drawn directly on a terminal instead of being rendered as an event
+        let label = match &self.write {
+            Some(write) => write.config.redactions.redact(&label),
+            None => label,
+        };
+        self.steps.push(StepState {
+            label,
+            started: Instant::now(),
+            finished: None,
+            written: false,
+        });
+        self.draw();
+        self.steps.len() - 1
+    }
+
+    fn finish(&mut self, index: usize, finished: Finished) {
+        if let Some(step) = self.steps.get_mut(index) {
+            if step.finished.is_none() {
+                step.finished = Some(finished);
+            }
+        }
+        self.draw();
+    }
+
+    /// Draw all steps on a terminal, or write finished steps otherwise
+    fn draw(&mut self) {
+        let Some(write) = self.write.as_mut() else {
+            return;
+        };
+
+        if write.config.can_redraw() {
+            let mut frame = String::new();
+            if self.drawn > 0 {
+                // Move to the start of the first row that was drawn
+                frame.push_str(&format!("\r\x1B[{}A", self.drawn));
+            }
+            let width = util::terminal_width().unwrap_or(DEFAULT_WIDTH);
+            let mut rows = 0;
+            for step in &self.steps {
+                let line = line(write, step, &self.ticks);
+                rows += ansi_escape::rows(&line, width);
+                frame.push_str(&line);
+                frame.push_str(CLEAR_LINE);
+                frame.push('\n');
+            }
+            write!(write, "{frame}").expect("writer open");
+            write.flush().expect("writer open");
+            self.drawn = rows;
+        } else {
+            for step in &mut self.steps {
+                if let (Some(finished), false) = (&step.finished, step.written) {
+                    step.written = true;
+                    render(write, Event::TimerStart(step.label.clone()));
+                    write!(write, "{}", dots(&self.ticks)).expect("writer open");
+                    render(write, finished_event(finished));
+                }
+            }
+        }
+    }
+
+    /// Finish any remaining steps and return the writer, later steps are ignored
+    fn take_write(&mut self) -> ParagraphInspectWrite<W> {
+        for step in &mut self.steps {
+            step.finished.get_or_insert(Finished::Error);
+        }
+        self.draw();
+
+        let write = self.write.take().expect("writer is only taken once");
+        if write.config.can_redraw() {
+            // Drawn without rendering, let sinks know what happened
+            let events = &write.config.events;
+            for step in &self.steps {
+                events.emit(Event::TimerStart(step.label.clone()));
+                if let Some(finished) = &step.finished {
+                    events.emit(finished_event(finished));
+                }
+            }
+        }
+        write
+    }
+}
+
+fn finished_event(finished: &Finished) -> Event {
+    match finished {
+        Finished::Done(duration) => Event::TimerDone(*duration),
+        Finished::Cancel(why) => Event::TimerCancel(why.clone()),
+        Finished::Error => Event::TimerCancel("Error".to_string()),
+    }
+}
+
+/// A single tick between the label and the outcome, the duration is shown after it
+fn dots(ticks: &TimerTicks) -> String {
+    format!("{}{}{}", ticks.start, ticks.tick, ticks.end)
+}
+
+/// A single line of a step as it would be rendered by the writer
+fn line<W>(write: &ParagraphInspectWrite<W>, step: &StepState, ticks: &TimerTicks) -> String {
+    let rendered = |event: Event| {
+        let mut buffer = ParagraphInspectWrite::with_terminal(Vec::new(), true);
+        buffer.set_color(ColorChoice::Always);
+        buffer.config.theme = write.config.theme.clone();
+        let config = buffer.config.clone();
+        write
+            .config
+            .renderer
+            .render(&mut Output::new(&mut buffer, config), &event);
+        String::from_utf8_lossy(&buffer.inner)
+            .trim_end_matches('\n')
+            .to_string()
+    };
+
+    let mut line = rendered(Event::TimerStart(step.label.clone()));
+    match &step.finished {
+        Some(finished) => {
+            line.push_str(&dots(ticks));
+            line.push_str(&rendered(finished_event(finished)));
+        }
+        None => {
+            line.push_str(&ticks.start);
+            line.push_str(&ticks.tick);
+            line.push_str(&ansi_escape::wrap_ansi_escape_each_line(
+                &write.config.theme.dim,
+                format!(" {}", duration_format::human(&step.started.elapsed())),
+            ));
+        }
+    }
+    line
+}
+
+/// Receives the outcome of steps
+trait Steps: Send {
+    fn finish(&mut self, index: usize, finished: Finished);
+}
+
+impl<W: Write + Send> Steps for Display<W> {
+    fn finish(&mut self, index: usize, finished: Finished) {
+        Display::finish(self, index, finished);
+    }
+}
+
+/// A labelled step of a parallel group with its own timer.
+///
+/// Finish it with [`Step::done`] or [`Step::cancel`] from any thread. If it is dropped without
+/// being finished it is shown as an error.
+pub struct Step {
+    index: usize,
+    display: Arc<Mutex<dyn Steps>>,
+    finished: bool,
+    started: Instant,
+}
+
+impl Debug for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Step")
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Step {
+    /// Finish the step, showing how long it took
+    pub fn done(mut self) {
+        let duration = self.started.elapsed();
+        self.finish(Finished::Done(duration));
+    }
+
+    /// Interrupt the step with a message explaining why
+    pub fn cancel(mut self, why_details: impl AsRef<str>) {
+        self.finish(Finished::Cancel(why_details.as_ref().to_string()));
+    }
+
+    fn finish(&mut self, finished: Finished) {
+        self.finished = true;
+        self.display
+            .lock()
+            .expect("Parallel display lock poisoned - cannot guarantee data consistency")
+            .finish(self.index, finished);
+    }
+}
+
+impl Drop for Step {
+    fn drop(&mut self) {
+        if !self.finished {
+            // Drop implementations must not panic, skip output if the lock is poisoned
+            if let Ok(mut display) = self.display.lock() {
+                display.finish(self.index, Finished::Error);
+            }
+        }
+    }
+}
+
+/// Shared state of a parallel group
+pub(crate) struct Group<W> {
+    display: Arc<Mutex<Display<W>>>,
+    redraw: Option<RedrawGuard>,
+}
+
+impl<W> Debug for Group<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Group").finish_non_exhaustive()
+    }
+}
+
+impl<W> Group<W>
+where
+    W: Write + Send + 'static,
+{
+    pub(crate) fn start(write: ParagraphInspectWrite<W>) -> Self {
+        let ticks = write.config.renderer.timer_ticks(&write.config.theme);
+        let is_terminal = write.config.can_redraw();
+        let display = Arc::new(Mutex::new(Display {
+            write: Some(write),
+            steps: Vec::new(),
+            ticks,
+            drawn: 0,
+        }));
+        let redraw = is_terminal.then(|| RedrawGuard::start(display.clone()));
+        Self { display, redraw }
+    }
+
+    pub(crate) fn step(&mut self, label: impl AsRef<str>) -> Step {
+        let index = self
+            .display
+            .lock()
+            .expect("Parallel display lock poisoned - cannot guarantee data consistency")
+            .add(label.as_ref().to_string());
+        Step {
+            index,
+            display: self.display.clone(),
+            finished: false,
+            started: Instant::now(),
+        }
+    }
+
+    pub(crate) fn done(mut self) -> ParagraphInspectWrite<W> {
+        drop(self.redraw.take());
+        self.display
+            .lock()
+            .expect("Parallel display lock poisoned - cannot guarantee data consistency")
+            .take_write()
+    }
+}
+
+/// Redraws a terminal in the background until dropped
+struct RedrawGuard {
+    stop_signal: Sender<()>,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl RedrawGuard {
+    fn start<W: Write + Send + 'static>(display: Arc<Mutex<Display<W>>>) -> Self {
+        let (sender, receiver) = channel::<()>();
+        let join_handle = std::thread::spawn(move || {
+            while receiver.recv_timeout(INTERVAL).is_err() {
+                match display.lock() {
+                    Ok(mut display) => display.draw(),
+                    Err(_) => break,
+                }
+            }
+        });
+        Self {
+            stop_signal: sender,
+            join_handle: Some(join_handle),
+        }
+    }
+}
+
+impl Drop for RedrawGuard {
+    fn drop(&mut self) {
+        // Signal AND wait for the thread so it can't draw over output that comes after the group
+        let _ = self.stop_signal.send(());
+        if let Some(join_handle) = self.join_handle.take() {
+            let _ = join_handle.join();
+        }
+    }
+}
+
+/// A labelled source of output streamed at the same time as others.
+///
+/// Used with [`crate::Print::stream_many_with`] and [`crate::Print::stream_cmds`]. Every line the
+/// source writes is prefixed with its label, padded to the longest label, so interleaved output
+/// can be told apart:
+///
+/// ```text
+///   - Running npm install
+///
+///       web | added 12 packages
+///       api | added 3 packages
+///
+///   - Done (web exit status 0 in 1.2s, api exit status 0 in 0.8s)
+/// ```
+pub struct Labelled<T> {
+    pub(crate) label: String,
+    pub(crate) color: Option<ANSI>,
+    pub(crate) inner: T,
+}
+
+impl<T> Labelled<T> {
+    /// Label a closure or command
+    pub fn new(label: impl Into<String>, inner: T) -> Self {
+        Self {
+            label: label.into(),
+            color: None,
+            inner,
+        }
+    }
+
+    /// Color the label with SGR parameters such as `"1;35"`
+    #[must_use]
+    pub fn color(mut self, sgr: impl AsRef<str>) -> Self {
+        self.color = Some(ANSI::from_sgr(sgr));
+        self
+    }
+
+    /// Prefix written before every line, the label is padded to `width` visible columns
+    pub(crate) fn prefix(&self, width: usize) -> String {
+        let padding = " ".repeat(width.saturating_sub(visible_width(&self.label)));
+        let label = match &self.color {
+            Some(color) => ansi_escape::wrap_ansi_escape_each_line(color, &self.label),
+            None => self.label.clone(),
+        };
+        format!("{label}{padding} | ")
+    }
+}
+
+impl<T> Debug for Labelled<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Labelled")
+            .field("label", &self.label)
+            .field("color", &self.color)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::strip_ansi;
+    use crate::Print;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn completion_order_when_not_a_terminal() {
+        let mut group = Print::new(Vec::new())
+            .without_header()
+            .bullet("Parallel")
+            .start_parallel();
+        let first = group.step("First");
+        let second = group.step("Second");
+        let third = group.step("Third");
+
+        second.cancel("Skipped");
+        first.done();
+        drop(third);
+
+        let output = group.done().sub_bullet("After").done().done();
+        assert_eq!(
+            indoc::indoc! {"
+                - Parallel
+                  - Second ... (Skipped)
+                  - First ... (< 0.1s)
+                  - Third ... (Error)
+                  - After
+                - Done (finished in < 0.1s)
+            "},
+            strip_ansi(String::from_utf8_lossy(&output))
+        );
+    }
+
+    #[test]
+    fn unfinished_steps_are_errors() {
+        let mut group = Print::new(Vec::new())
+            .without_header()
+            .bullet("Parallel")
+            .start_parallel();
+        let step = group.step("Forgotten");
+
+        let output = group.done().done().done();
+        step.done();
+        assert_eq!(
+            "- Parallel\n  - Forgotten ... (Error)\n- Done (finished in < 0.1s)\n",
+            strip_ansi(String::from_utf8_lossy(&output))
+        );
+    }
+
+    #[test]
+    fn redraws_on_a_terminal() {
+        let write = crate::util::ParagraphInspectWrite::with_terminal(Vec::new(), true);
+        let mut group = super::Group::start(write);
+        let first = group.step("First");
+        let second = group.step("Second");
+        first.done();
+        second.done();
+
+        let output = crate::ansi_escape::strip_colors(String::from_utf8_lossy(&group.done().inner));
+        let last_frame = output.rsplit("\r\x1B[2A").next().unwrap();
+        assert_eq!(
+            "  - First ... (< 0.1s)\x1B[K\n  - Second ... (< 0.1s)\x1B[K\n",
+            last_frame
+        );
+    }
+
+    #[test]
+    fn counts_rows_of_long_and_multiline_labels() {
+        let write = crate::util::ParagraphInspectWrite::with_terminal(Vec::new(), true);
+        let mut group = super::Group::start(write);
+        let long = "x".repeat(300);
+        group.step("Multi\nline\r\nlabel").done();
+        group.step(&long).done();
+
+        let output = crate::ansi_escape::strip_colors(String::from_utf8_lossy(&group.done().inner));
+        let (before, last_frame) = output.rsplit_once("\r\x1B[").unwrap();
+        let previous_frame = before.rsplit("\r\x1B[").next().unwrap();
+        let previous_frame = &previous_frame[previous_frame.find('A').unwrap() + 1..];
+        let width = crate::util::terminal_width().unwrap_or(super::DEFAULT_WIDTH);
+        let rows: usize = previous_frame
+            .lines()
+            .map(|line| crate::ansi_escape::rows(line, width))
+            .sum();
+        assert!(rows > 2, "{output:?}");
+        assert!(last_frame.starts_with(&format!("{rows}A")), "{output:?}");
+        assert!(
+            last_frame.contains("  - Multi line label ... (< 0.1s)"),
+            "{output:?}"
+        );
+    }
+}
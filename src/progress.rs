@@ -0,0 +1,371 @@
+//! Report progress while reading or writing
+//!
+//! Wrap a [`Read`] or [`Write`] such as an HTTP response or a file to report the number of bytes
+//! transferred without manual bookkeeping. Adapters are created from a [`crate::Print`] in the
+//! [`crate::state::Progress`] state, a [`crate::GlobalProgress`], or with
+//! [`crate::global::print::sub_progress_reader`] and [`crate::global::print::sub_progress_writer`].
+//!
+//! ```
+//! use bullet_stream::Print;
+//!
+//! let response = std::io::repeat(b'a');
+//! let mut file = Vec::new();
+//!
+//! let mut progress = Print::new(Vec::new())
+//!     .without_header()
+//!     .bullet("Ruby version")
+//!     .start_progress("Downloading", 4096);
+//!
+//! std::io::copy(&mut progress.reader(std::io::Read::take(response, 4096)), &mut file).unwrap();
+//!
+//! let output = progress.done().done().done();
+//! let output = bullet_stream::strip_ansi(String::from_utf8_lossy(&output));
+//! assert!(output.contains("  - Downloading ............ (< 0.1s)\n"));
+//! assert_eq!(4096, file.len());
+//! ```
+use crate::ansi_escape::{self, ANSI, BACKSPACE, CLEAR_LINE};
+use crate::byte_format;
+use crate::event::Event;
+use crate::render::{render, TimerTicks};
+use crate::util::TrailingParagraph;
+use crate::write;
+use crate::GlobalProgress;
+use std::fmt::Debug;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+/// Number of dots written over the course of a progress bar when redrawing is not possible
+const MILESTONES: u64 = 10;
+/// Limit how often a terminal is redrawn
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress towards a known total, such as bytes downloaded.
+///
+/// On a terminal the percentage, bytes, and rate are redrawn in place after the message. Otherwise
+/// the timer ticks of the renderer are written at each 10% milestone so output is append-only.
+#[derive(Debug)]
+pub(crate) struct ProgressBar {
+    started: Instant,
+    total: u64,
+    current: u64,
+    is_terminal: bool,
+    ticks: TimerTicks,
+    dim: ANSI,
+    milestones: u64,
+    drawn: usize,
+    last_draw: Option<Instant>,
+}
+
+impl ProgressBar {
+    pub(crate) fn start<W: TrailingParagraph>(
+        writer: &mut W,
+        s: impl AsRef<str>,
+        total: u64,
+    ) -> Self {
+        let ticks = write::timer_start(writer, s);
+        let config = writer.config();
+        let mut bar = Self {
+            started: Instant::now(),
+            total,
+            current: 0,
+            is_terminal: config.can_redraw(),
+            ticks,
+            dim: config.theme.dim.clone(),
+            milestones: 0,
+            drawn: 0,
+            last_draw: None,
+        };
+        if bar.is_terminal {
+            bar.draw(writer);
+        } else {
+            write!(writer, "{}", bar.ticks.start).expect("writer open");
+            writer.flush().expect("writer open");
+        }
+        bar
+    }
+
+    pub(crate) fn inc<W: Write>(&mut self, writer: &mut W, n: u64) {
+        self.current = self.current.saturating_add(n);
+        if self.is_terminal {
+            if self
+                .last_draw
+                .is_none_or(|last| last.elapsed() >= REDRAW_INTERVAL)
+            {
+                self.draw(writer);
+            }
+        } else {
+            let reached = self.percent() * MILESTONES / 100;
+            while self.milestones < reached {
+                self.milestones += 1;
+                write!(writer, "{}", self.ticks.tick).expect("writer open");
+            }
+            writer.flush().expect("writer open");
+        }
+    }
+
+    pub(crate) fn done<W: TrailingParagraph>(self, writer: &mut W) {
+        if self.is_terminal {
+            write!(
+                writer,
+                "{}{CLEAR_LINE}{}",
+                BACKSPACE.repeat(self.drawn),
+                self.ticks.start
+            )
+            .expect("writer open");
+        }
+        write!(writer, "{}", self.ticks.end).expect("writer open");
+        render(writer, Event::TimerDone(self.started.elapsed()));
+    }
+
+    fn percent(&self) -> u64 {
+        if self.total == 0 {
+            100
+        } else {
+            (u128::from(self.current) * 100 / u128::from(self.total)).min(100) as u64
+        }
+    }
+
+    fn status(&self) -> String {
+        let rate = self.current as f64 / self.started.elapsed().as_secs_f64().max(0.001);
+        format!(
+            " {}% {} / {} ({}/s)",
+            self.percent(),
+            byte_format::human(self.current),
+            byte_format::human(self.total),
+            byte_format::human(rate as u64)
+        )
+    }
+
+    fn draw<W: Write>(&mut self, writer: &mut W) {
+        let status = self.status();
+        write!(
+            writer,
+            "{}{}{CLEAR_LINE}",
+            BACKSPACE.repeat(self.drawn),
+            ansi_escape::wrap_ansi_escape_each_line(&self.dim, &status)
+        )
+        .expect("writer open");
+        writer.flush().expect("writer open");
+        self.drawn = status.chars().count();
+        self.last_draw = Some(Instant::now());
+    }
+}
+
+/// Receives the number of bytes transferred by an adapter
+pub(crate) trait Progressed {
+    fn inc(&mut self, n: u64);
+
+    /// Called once the transfer is complete
+    fn finish(&mut self) {}
+}
+
+impl Progressed for GlobalProgress {
+    fn inc(&mut self, n: u64) {
+        GlobalProgress::inc(self, n);
+    }
+}
+
+impl<P: Progressed + ?Sized> Progressed for &mut P {
+    fn inc(&mut self, n: u64) {
+        (**self).inc(n);
+    }
+
+    fn finish(&mut self) {
+        (**self).finish();
+    }
+}
+
+/// Finishes the progress output when the transfer is complete
+#[cfg(feature = "global_functions")]
+pub(crate) struct Finishing(pub(crate) Option<GlobalProgress>);
+
+#[cfg(feature = "global_functions")]
+impl Progressed for Finishing {
+    fn inc(&mut self, n: u64) {
+        if let Some(progress) = &mut self.0 {
+            progress.inc(n);
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(progress) = self.0.take() {
+            progress.done();
+        }
+    }
+}
+
+/// A [`Read`] that reports the number of bytes read as progress.
+///
+/// When created with [`crate::global::print::sub_progress_reader`] the progress output is
+/// finished when the end of the input is reached or the reader is dropped.
+pub struct ProgressRead<'a, R> {
+    inner: R,
+    progress: Box<dyn Progressed + Send + 'a>,
+}
+
+impl<'a, R> ProgressRead<'a, R> {
+    pub(crate) fn new(inner: R, progress: impl Progressed + Send + 'a) -> Self {
+        Self {
+            inner,
+            progress: Box::new(progress),
+        }
+    }
+}
+
+impl<R: Read> Read for ProgressRead<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n == 0 && !buf.is_empty() {
+            self.progress.finish();
+        } else {
+            self.progress.inc(n as u64);
+        }
+        Ok(n)
+    }
+}
+
+impl<R> Drop for ProgressRead<'_, R> {
+    fn drop(&mut self) {
+        self.progress.finish();
+    }
+}
+
+impl<R: Debug> Debug for ProgressRead<'_, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressRead")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`Write`] that reports the number of bytes written as progress.
+///
+/// When created with [`crate::global::print::sub_progress_writer`] the progress output is
+/// finished when the writer is dropped.
+pub struct ProgressWrite<'a, W: Write> {
+    inner: W,
+    progress: Box<dyn Progressed + Send + 'a>,
+}
+
+impl<'a, W: Write> ProgressWrite<'a, W> {
+    pub(crate) fn new(inner: W, progress: impl Progressed + Send + 'a) -> Self {
+        Self {
+            inner,
+            progress: Box::new(progress),
+        }
+    }
+}
+
+impl<W: Write> Write for ProgressWrite<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.progress.inc(n as u64);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for ProgressWrite<'_, W> {
+    fn drop(&mut self) {
+        // Drop implementations must not panic. We intentionally ignore the potential error here.
+        let _result = self.inner.flush();
+        self.progress.finish();
+    }
+}
+
+impl<W: Write + Debug> Debug for ProgressWrite<'_, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressWrite")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::strip_ansi;
+    use crate::util::ParagraphInspectWrite;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn milestone_dots_when_not_a_terminal() {
+        let mut writer = ParagraphInspectWrite::with_terminal(Vec::new(), false);
+        let mut bar = ProgressBar::start(&mut writer, "Downloading", 1000);
+        bar.inc(&mut writer, 250);
+        assert_eq!(
+            "  - Downloading ...",
+            strip_ansi(String::from_utf8_lossy(&writer.inner))
+        );
+
+        bar.inc(&mut writer, 750);
+        bar.done(&mut writer);
+        assert_eq!(
+            "  - Downloading ............ (< 0.1s)\n",
+            strip_ansi(String::from_utf8_lossy(&writer.inner))
+        );
+    }
+
+    #[test]
+    fn global_reader_finishes_at_end_of_input() {
+        let output = crate::global::with_locked_writer(Vec::new(), || {
+            let mut file = Vec::new();
+            let mut reader = crate::global::print::sub_progress_reader(
+                "Downloading",
+                std::io::Read::take(std::io::repeat(b'a'), 100),
+                100,
+            );
+            std::io::copy(&mut reader, &mut file).unwrap();
+            crate::global::print::sub_bullet("Next");
+            drop(reader);
+        });
+
+        assert_eq!(
+            "  - Downloading ............ (< 0.1s)\n  - Next\n",
+            strip_ansi(String::from_utf8_lossy(&output))
+        );
+    }
+
+    #[test]
+    fn global_writer_finishes_on_drop() {
+        let output = crate::global::with_locked_writer(Vec::new(), || {
+            let mut writer =
+                crate::global::print::sub_progress_writer("Extracting", Vec::new(), 10);
+            writer.write_all(b"hello").unwrap();
+            drop(writer);
+        });
+
+        assert_eq!(
+            "  - Extracting ....... (< 0.1s)\n",
+            strip_ansi(String::from_utf8_lossy(&output))
+        );
+    }
+
+    #[test]
+    fn redraws_on_a_terminal() {
+        let mut writer = ParagraphInspectWrite::with_terminal(Vec::new(), true);
+        let mut bar = ProgressBar::start(&mut writer, "Downloading", 2048);
+        let first = " 0% 0 B / 2.0 KiB (0 B/s)";
+        bar.last_draw = None;
+        bar.inc(&mut writer, 1024);
+        let output = crate::ansi_escape::strip_colors(String::from_utf8_lossy(&writer.inner));
+        let (start, second) = output
+            .split_once(&format!(
+                "{first}{CLEAR_LINE}{}",
+                BACKSPACE.repeat(first.len())
+            ))
+            .unwrap();
+        assert_eq!("  - Downloading", start);
+        assert!(second.starts_with(" 50% 1.0 KiB / 2.0 KiB ("), "{second}");
+
+        bar.done(&mut writer);
+        let output = crate::ansi_escape::strip_colors(String::from_utf8_lossy(&writer.inner));
+        assert!(
+            output.ends_with(&format!("{CLEAR_LINE} .. (< 0.1s)\n")),
+            "{output:?}"
+        );
+    }
+}
@@ -0,0 +1,750 @@
+//! Record output and replay it later
+//!
+//! A [`Recorder`] is an [`EventSink`] that keeps every [`Event`], including each line of streamed
+//! output, along with when it happened. The resulting [`Recording`] can be saved as text and
+//! replayed onto any writer with [`crate::Print::replay`] or [`crate::global::print::replay`].
+//! The output is rendered again, so it can be replayed with or without color, wrapped to a
+//! different width, or with a different [`crate::render::Renderer`].
+//!
+//! ```
+//! use bullet_stream::{record::{Playback, Recorder, Recording}, Print};
+//!
+//! let recorder = Recorder::new();
+//! let original = Print::new(Vec::new())
+//!     .with_events(recorder.clone())
+//!     .h2("Example Buildpack")
+//!     .bullet("Ruby version")
+//!     .sub_bullet("Using 3.4.1")
+//!     .done()
+//!     .done();
+//!
+//! // Save the recording, for example to a file
+//! let saved = recorder.recording().to_string();
+//!
+//! let recording = saved.parse::<Recording>().unwrap();
+//! let replayed = Print::new(Vec::new())
+//!     .replay(&recording, Playback::Instant);
+//!
+//! assert_eq!(original, replayed);
+//! ```
+//!
+//! ## Format
+//!
+//! A recording is [JSON Lines](https://jsonlines.org/). The first line identifies the format and
+//! version, every other line is one event:
+//!
+//! - `at_ms`: Milliseconds since the first event
+//! - `type`: The [`Event::name`]
+//! - `depth`: The [`Event::depth`] (nested bullets only)
+//! - `text`: The [`Event::text`] including ANSI escape codes (when present)
+//! - `duration_ms`: The [`Event::duration`] in milliseconds (when present)
+//!
+//! Fields that are not recognized are ignored so new fields can be added without changing the
+//! version.
+use crate::background_printer::{Tick, Ticker};
+use crate::event::{json_string, Event, EventSink};
+use crate::render::render;
+use crate::timer::TimerOptions;
+use crate::util::TrailingParagraph;
+use crate::write;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Identifies a recording, written as the first line
+const HEADER: &str = r#"{"format":"bullet_stream_recording","version":1}"#;
+/// Timers are replayed with the default interval since the original interval is not recorded
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An [`EventSink`] that records events to be replayed later.
+///
+/// Clones share the same recording, register one with [`crate::Print::with_events`] or
+/// [`crate::global::add_event_sink`] and keep another to get the [`Recording`].
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    state: Arc<Mutex<RecorderState>>,
+}
+
+#[derive(Debug, Default)]
+struct RecorderState {
+    first: Option<SystemTime>,
+    entries: Vec<Entry>,
+}
+
+impl Recorder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything recorded so far
+    ///
+    /// # Panics
+    ///
+    /// If a thread panicked while recording
+    #[must_use]
+    pub fn recording(&self) -> Recording {
+        let state = self
+            .state
+            .lock()
+            .expect("Recorder lock poisoned - cannot guarantee data consistency");
+        Recording {
+            entries: state.entries.clone(),
+        }
+    }
+}
+
+impl EventSink for Recorder {
+    fn event(&mut self, at: SystemTime, event: &Event) {
+        let mut state = self
+            .state
+            .lock()
+            .expect("Recorder lock poisoned - cannot guarantee data consistency");
+        let first = *state.first.get_or_insert(at);
+        state.entries.push(Entry {
+            at: at.duration_since(first).unwrap_or_default(),
+            event: event.clone(),
+        });
+    }
+}
+
+/// A single recorded event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Time since the first event
+    pub at: Duration,
+    pub event: Event,
+}
+
+/// Recorded events that can be replayed.
+///
+/// Use [`Display`] (`to_string`) to save it and [`FromStr`] (`parse`) to load it again. See the
+/// [module docs](self) for the format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recording {
+    entries: Vec<Entry>,
+}
+
+impl Recording {
+    /// The recorded events in order
+    #[must_use]
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+}
+
+impl From<Vec<Entry>> for Recording {
+    fn from(entries: Vec<Entry>) -> Self {
+        Self { entries }
+    }
+}
+
+impl Display for Recording {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        for Entry { at, event } in &self.entries {
+            write!(
+                f,
+                r#"{{"at_ms":{},"type":"{}""#,
+                at.as_millis(),
+                event.name()
+            )?;
+            if let Event::NestedBullet { depth, .. } = event {
+                write!(f, r#","depth":{depth}"#)?;
+            }
+            if let Some(text) = event.text() {
+                write!(f, r#","text":{}"#, json_string(text))?;
+            }
+            if let Some(duration) = event.duration() {
+                write!(f, r#","duration_ms":{}"#, duration.as_millis())?;
+            }
+            writeln!(f, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Recording {
+    type Err = ParseRecordingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line))
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        let (number, header) = lines.next().ok_or(ParseRecordingError {
+            line: 1,
+            message: String::from("missing header"),
+        })?;
+        let header = Fields::parse(header).map_err(|message| ParseRecordingError {
+            line: number,
+            message,
+        })?;
+        if header.string("format") != Some("bullet_stream_recording") {
+            return Err(ParseRecordingError {
+                line: number,
+                message: String::from("not a bullet_stream recording"),
+            });
+        }
+        if header.number("version") != Some(1) {
+            return Err(ParseRecordingError {
+                line: number,
+                message: String::from("unsupported version, expected 1"),
+            });
+        }
+
+        lines
+            .map(|(number, line)| {
+                Fields::parse(line)
+                    .and_then(|fields| fields.entry())
+                    .map_err(|message| ParseRecordingError {
+                        line: number,
+                        message,
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Recording::from)
+    }
+}
+
+/// A recording could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseRecordingError {
+    /// Line number starting at `1`
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ParseRecordingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid recording on line {}: {}",
+            self.line, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseRecordingError {}
+
+/// How fast a recording is replayed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Playback {
+    /// Write everything as fast as possible (default)
+    #[default]
+    Instant,
+    /// Wait between events as long as the original did, timers tick while they're running
+    RealTime,
+}
+
+/// Render a recording to the writer
+pub(crate) fn replay<W: TrailingParagraph>(
+    writer: &mut W,
+    recording: &Recording,
+    playback: Playback,
+) {
+    let mut player = Player {
+        started: Instant::now(),
+        playback,
+        timer: None,
+    };
+    for Entry { at, event } in &recording.entries {
+        player.wait(writer, *at);
+        match event {
+            Event::StreamOutput(line) => {
+                let renderer = writer.config().renderer;
+                write::stream_line(writer, renderer.as_ref(), None, line.clone().into_bytes());
+            }
+            Event::TimerStart(s) => {
+                let (ticks, _, wrap) = write::timer_start_with(writer, s, &TimerOptions::default());
+                let mut ticker = Ticker::start(
+                    writer,
+                    ticks.start,
+                    Tick::Append(ticks.tick),
+                    ticks.end,
+                    wrap,
+                );
+                ticker.tick(writer);
+                player.timer = Some(Timer {
+                    at: *at,
+                    ticks: 1,
+                    ticker,
+                });
+            }
+            Event::TimerDone(_) | Event::TimerCancel(_) => {
+                if let Some(mut timer) = player.timer.take() {
+                    let duration = event.duration().unwrap_or(at.saturating_sub(timer.at));
+                    while timer.ticks <= duration.as_secs() {
+                        timer.tick(writer);
+                    }
+                    timer.ticker.stop(writer);
+                }
+                render(writer, event.clone());
+            }
+            event => render(writer, event.clone()),
+        }
+    }
+}
+
+/// Tracks time and running timers while replaying
+struct Player {
+    started: Instant,
+    playback: Playback,
+    timer: Option<Timer>,
+}
+
+impl Player {
+    /// Wait until `at` when playing in real time, ticking a running timer meanwhile
+    fn wait<W: TrailingParagraph>(&mut self, writer: &mut W, at: Duration) {
+        if self.playback == Playback::Instant {
+            return;
+        }
+        loop {
+            match &mut self.timer {
+                Some(timer) if timer.next() < at => {
+                    std::thread::sleep(timer.next().saturating_sub(self.started.elapsed()));
+                    timer.tick(writer);
+                }
+                _ => {
+                    std::thread::sleep(at.saturating_sub(self.started.elapsed()));
+                    break;
+                }
+            }
+        }
+    }
+}
+
+struct Timer {
+    /// When the timer started
+    at: Duration,
+    /// Number of ticks written
+    ticks: u64,
+    ticker: Ticker,
+}
+
+impl Timer {
+    /// When the next tick is due
+    fn next(&self) -> Duration {
+        self.at + TICK_INTERVAL * u32::try_from(self.ticks).unwrap_or(u32::MAX)
+    }
+
+    fn tick<W: TrailingParagraph>(&mut self, writer: &mut W) {
+        self.ticker.tick(writer);
+        self.ticks += 1;
+    }
+}
+
+/// The strings and numbers of a JSON object, as written by [`Recording`]
+///
+/// Other values such as `null` or nested objects are checked for valid syntax, then ignored.
+#[derive(Debug, Default)]
+struct Fields(Vec<(String, Value)>);
+
+#[derive(Debug)]
+enum Value {
+    String(String),
+    Number(u64),
+    Other,
+}
+
+impl Fields {
+    fn string(&self, key: &str) -> Option<&str> {
+        self.0.iter().find_map(|(name, value)| match value {
+            Value::String(s) if name == key => Some(s.as_str()),
+            _ => None,
+        })
+    }
+
+    fn number(&self, key: &str) -> Option<u64> {
+        self.0.iter().find_map(|(name, value)| match value {
+            Value::Number(n) if name == key => Some(*n),
+            _ => None,
+        })
+    }
+
+    fn entry(&self) -> Result<Entry, String> {
+        let at = Duration::from_millis(self.number("at_ms").ok_or("missing at_ms")?);
+        let name = self.string("type").ok_or("missing type")?;
+        let text = || {
+            self.string("text")
+                .map(str::to_string)
+                .ok_or(format!("missing text for {name}"))
+        };
+        let duration = || {
+            self.number("duration_ms")
+                .map(Duration::from_millis)
+                .ok_or(format!("missing duration_ms for {name}"))
+        };
+        let event = match name {
+            "h1" => Event::H1(text()?),
+            "h2" => Event::H2(text()?),
+            "h3" => Event::H3(text()?),
+            "plain" => Event::Plain(text()?),
+            "bullet" => Event::Bullet(text()?),
+            "bullet_done" => Event::BulletDone,
+            "sub_bullet" => Event::SubBullet(text()?),
+            "nested_bullet" => Event::NestedBullet {
+                depth: self
+                    .number("depth")
+                    .and_then(|depth| usize::try_from(depth).ok())
+                    .ok_or("missing depth for nested_bullet")?,
+                text: text()?,
+            },
+            "warning" => Event::Warning(text()?),
+            "error" => Event::Error(text()?),
+            "important" => Event::Important(text()?),
+            "stream_start" => Event::StreamStart(text()?),
+            "stream_output" => Event::StreamOutput(text()?),
+            "stream_done" => Event::StreamDone(duration()?),
+            "streams_done" => Event::StreamsDone {
+                duration: duration()?,
+                summary: text()?,
+            },
+            "timer_start" => Event::TimerStart(text()?),
+            "timer_done" => Event::TimerDone(duration()?),
+            "timer_cancel" => Event::TimerCancel(text()?),
+            "all_done" => Event::AllDone(duration().ok()),
+            other => return Err(format!("unknown type {other:?}")),
+        };
+        Ok(Entry { at, event })
+    }
+
+    fn parse(line: &str) -> Result<Self, String> {
+        let mut chars = line.trim().chars().peekable();
+        let mut fields = Fields::default();
+        if chars.next() != Some('{') {
+            return Err(String::from("expected an object"));
+        }
+        skip_whitespace(&mut chars);
+        if chars.peek() == Some(&'}') {
+            chars.next();
+        } else {
+            loop {
+                skip_whitespace(&mut chars);
+                let key = parse_string(&mut chars)?;
+                skip_whitespace(&mut chars);
+                if chars.next() != Some(':') {
+                    return Err(format!("expected ':' after {key:?}"));
+                }
+                skip_whitespace(&mut chars);
+                let value = match chars.peek() {
+                    Some('"') => Value::String(parse_string(&mut chars)?),
+                    Some(c) if c.is_ascii_digit() || *c == '-' => {
+                        let number = parse_number(&mut chars)?;
+                        if number.bytes().all(|byte| byte.is_ascii_digit()) {
+                            Value::Number(number.parse().map_err(|_| "number too large")?)
+                        } else {
+                            Value::Other
+                        }
+                    }
+                    _ => {
+                        skip_value(&mut chars)?;
+                        Value::Other
+                    }
+                };
+                fields.0.push((key, value));
+                skip_whitespace(&mut chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some('}') => break,
+                    _ => return Err(String::from("expected ',' or '}'")),
+                }
+            }
+        }
+        skip_whitespace(&mut chars);
+        if chars.next().is_some() {
+            return Err(String::from("unexpected text after object"));
+        }
+        Ok(fields)
+    }
+}
+
+fn skip_whitespace(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn parse_string(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Result<String, String> {
+    if chars.next() != Some('"') {
+        return Err(String::from("expected a string"));
+    }
+    let mut result = String::new();
+    loop {
+        match chars.next().ok_or("unterminated string")? {
+            '"' => return Ok(result),
+            '\\' => match chars.next().ok_or("unterminated string")? {
+                c @ ('"' | '\\' | '/') => result.push(c),
+                'n' => result.push('\n'),
+                'r' => result.push('\r'),
+                't' => result.push('\t'),
+                'b' => result.push('\u{8}'),
+                'f' => result.push('\u{c}'),
+                'u' => {
+                    let mut code = parse_hex(chars)?;
+                    if (0xD800..0xDC00).contains(&code) {
+                        if chars.next() != Some('\\') || chars.next() != Some('u') {
+                            return Err(String::from("unpaired surrogate"));
+                        }
+                        let low = parse_hex(chars)?;
+                        if !(0xDC00..0xE000).contains(&low) {
+                            return Err(String::from("unpaired surrogate"));
+                        }
+                        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                    }
+                    result.push(char::from_u32(code).ok_or("invalid unicode escape")?);
+                }
+                c => return Err(format!("invalid escape \\{c}")),
+            },
+            c => result.push(c),
+        }
+    }
+}
+
+fn parse_hex(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Result<u32, String> {
+    let hex = chars.take(4).collect::<String>();
+    if hex.len() != 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid unicode escape {hex:?}"));
+    }
+    u32::from_str_radix(&hex, 16).map_err(|_| format!("invalid unicode escape {hex:?}"))
+}
+
+/// Returns the text of a number such as `12` or `-1.5e3`
+fn parse_number(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Result<String, String> {
+    let mut number = String::new();
+    while let Some(c) =
+        chars.next_if(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+    {
+        number.push(c);
+    }
+    if number.parse::<f64>().is_err() {
+        return Err(format!("invalid number {number:?}"));
+    }
+    Ok(number)
+}
+
+/// Check the syntax of a value that isn't used, such as `null` or a nested object
+fn skip_value(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Result<(), String> {
+    match chars.peek() {
+        Some('"') => parse_string(chars).map(drop),
+        Some(c) if c.is_ascii_digit() || *c == '-' => parse_number(chars).map(drop),
+        Some('{' | '[') => {
+            let close = if chars.next() == Some('{') { '}' } else { ']' };
+            skip_whitespace(chars);
+            if chars.next_if_eq(&close).is_some() {
+                return Ok(());
+            }
+            loop {
+                skip_whitespace(chars);
+                if close == '}' {
+                    let key = parse_string(chars)?;
+                    skip_whitespace(chars);
+                    if chars.next() != Some(':') {
+                        return Err(format!("expected ':' after {key:?}"));
+                    }
+                    skip_whitespace(chars);
+                }
+                skip_value(chars)?;
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some(c) if c == close => return Ok(()),
+                    _ => return Err(format!("expected ',' or '{close}'")),
+                }
+            }
+        }
+        _ => {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(char::is_ascii_alphabetic) {
+                word.push(c);
+            }
+            match word.as_str() {
+                "true" | "false" | "null" => Ok(()),
+                _ => Err(format!("unexpected value {word:?}")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{strip_ansi, ColorChoice, Print, Wrapping};
+    use pretty_assertions::assert_eq;
+
+    fn record() -> (Vec<u8>, Recording) {
+        let recorder = Recorder::new();
+        let mut bullet = Print::new(Vec::new())
+            .with_color(ColorChoice::Always)
+            .with_events(recorder.clone())
+            .h2("Example Buildpack")
+            .bullet("Ruby version");
+        bullet.stream_with("Running \"installer\"", |mut stdout, _| {
+            writeln!(stdout, "tab\there\nunicode ⠋ \u{1}").unwrap();
+        });
+        let output = bullet
+            .start_timer("Installing")
+            .done()
+            .warning("Careful \\ with \"quotes\"")
+            .done()
+            .done();
+        (output, recorder.recording())
+    }
+
+    #[test]
+    fn round_trips() {
+        let (_, recording) = record();
+        let saved = recording.to_string();
+        assert!(saved.starts_with(&format!("{HEADER}\n")));
+        assert!(
+            saved.contains(r#""type":"warning","text":"Careful \\ with \"quotes\""}"#),
+            "{saved}"
+        );
+
+        // Times are saved in milliseconds
+        let parsed = saved.parse::<Recording>().unwrap();
+        assert_eq!(saved, parsed.to_string());
+        assert_eq!(recording.entries().len(), parsed.entries().len());
+    }
+
+    #[test]
+    fn replays_the_same_output() {
+        let (original, recording) = record();
+        let replayed = Print::new(Vec::new())
+            .with_color(ColorChoice::Always)
+            .replay(&recording, Playback::Instant);
+        assert_eq!(
+            String::from_utf8_lossy(&original),
+            String::from_utf8_lossy(&replayed)
+        );
+
+        let narrow = Print::new(Vec::new())
+            .with_color(ColorChoice::Never)
+            .with_wrap(Wrapping::Columns(12))
+            .replay(&recording, Playback::Instant);
+        let narrow = String::from_utf8_lossy(&narrow);
+        assert_eq!(strip_ansi(&narrow), narrow);
+        assert!(narrow.contains("\n! Careful \\\n! with\n"), "{narrow}");
+    }
+
+    #[test]
+    fn ticks_for_the_recorded_duration() {
+        let recording = Recording::from(vec![
+            Entry {
+                at: Duration::ZERO,
+                event: Event::Bullet("Ruby".to_string()),
+            },
+            Entry {
+                at: Duration::ZERO,
+                event: Event::TimerStart("Installing".to_string()),
+            },
+            Entry {
+                at: Duration::from_secs(3),
+                event: Event::TimerDone(Duration::from_secs(3)),
+            },
+        ]);
+        let output = Print::new(Vec::new()).replay(&recording, Playback::Instant);
+        assert_eq!(
+            "- Ruby\n  - Installing ...... (3.0s)\n",
+            strip_ansi(String::from_utf8_lossy(&output))
+        );
+    }
+
+    #[test]
+    fn real_time_waits_between_events() {
+        let recording = Recording::from(vec![
+            Entry {
+                at: Duration::ZERO,
+                event: Event::Bullet("One".to_string()),
+            },
+            Entry {
+                at: Duration::from_millis(50),
+                event: Event::Bullet("Two".to_string()),
+            },
+        ]);
+        let started = Instant::now();
+        let output = Print::new(Vec::new()).replay(&recording, Playback::RealTime);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert_eq!("- One\n- Two\n", String::from_utf8_lossy(&output));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            Err(ParseRecordingError {
+                line: 1,
+                message: String::from("not a bullet_stream recording")
+            }),
+            r#"{"format":"other","version":1}"#.parse::<Recording>()
+        );
+        assert_eq!(
+            Err(ParseRecordingError {
+                line: 3,
+                message: String::from(r#"unknown type "dance""#)
+            }),
+            format!("{HEADER}\n\n{{\"at_ms\":0,\"type\":\"dance\"}}").parse::<Recording>()
+        );
+        assert_eq!(
+            Ok(Recording::from(vec![Entry {
+                at: Duration::from_millis(5),
+                event: Event::AllDone(None)
+            }])),
+            format!("{HEADER}\n{{ \"at_ms\": 5, \"type\": \"all_done\", \"extra\": null }}")
+                .parse::<Recording>()
+        );
+    }
+
+    #[test]
+    fn strings_round_trip() {
+        for text in [
+            "",
+            "quote \" slash \\ solidus /",
+            "newline \n return \r tab \t bell \u{7}",
+            "\x1B[0;33mcolor\x1B[0m",
+            "wörld ⠋ 🎉",
+        ] {
+            let fields = Fields::parse(&format!(r#"{{"text":{}}}"#, json_string(text))).unwrap();
+            assert_eq!(Some(text), fields.string("text"));
+        }
+
+        let fields = Fields::parse(r#"{"text":"\u00e9 \ud83c\udf89 \/ \b\f"}"#).unwrap();
+        assert_eq!(Some("é 🎉 / \u{8}\u{c}"), fields.string("text"));
+    }
+
+    #[test]
+    fn skips_unused_values() {
+        let fields = Fields::parse(
+            r#"{"at_ms":1,"nested":{"a":[1,{"b":"},]"}],"c":{}},"list":[],"float":-1.5e3,"flag":true,"text":"a"}"#,
+        )
+        .unwrap();
+        assert_eq!(Some(1), fields.number("at_ms"));
+        assert_eq!(Some("a"), fields.string("text"));
+        assert_eq!(None, fields.number("float"));
+    }
+
+    #[test]
+    fn rejects_malformed_json() {
+        for line in [
+            "",
+            "[]",
+            r#"{"text":"unterminated}"#,
+            r#"{"text":"\x"}"#,
+            r#"{"text":"\u12"}"#,
+            r#"{"text":"\u+123"}"#,
+            r#"{"text":"\ud800"}"#,
+            r#"{"text":"\ud800\u0041"}"#,
+            r#"{"text" "a"}"#,
+            r#"{"text":"a",}"#,
+            r#"{"text":"a"} extra"#,
+            r#"{"nested":{"a":1}"#,
+            r#"{"nested":{"a" 1}}"#,
+            r#"{"list":[1,2}"#,
+            r#"{"flag":nul}"#,
+            r#"{"number":1.2.3}"#,
+            r#"{"at_ms":99999999999999999999999}"#,
+        ] {
+            assert!(Fields::parse(line).is_err(), "{line:?}");
+        }
+    }
+}
@@ -0,0 +1,285 @@
+//! Hide secrets such as tokens and passwords from output
+//!
+//! Register [`Redactions`] once with [`crate::Print::with_redactions`] or
+//! [`crate::global::set_redactions`]. Every match is replaced with [`REDACTED`] in headers,
+//! bullets, paragraphs, timers, streamed output, and the [`crate::event::Event`]s sent to sinks.
+//!
+//! ```
+//! use bullet_stream::{redact::Redactions, style, Print};
+//! use std::io::Write;
+//!
+//! let redactions = Redactions::new()
+//!     .secret("hunter2")
+//!     .pattern(r"ghp_[A-Za-z0-9]{8,}")
+//!     .unwrap();
+//!
+//! let mut stream = Print::new(Vec::new())
+//!     .with_redactions(redactions)
+//!     .without_header()
+//!     .bullet("Dependencies")
+//!     .sub_bullet(format!("Running {}", style::command("login --password hunter2")))
+//!     .start_stream("Installing");
+//! writeln!(stream, "Using token ghp_abcdef123456").unwrap();
+//!
+//! let output = stream.done().done().done();
+//! assert_eq!(
+//!     "- Dependencies\n  - Running `login --password [REDACTED]`\n  - Installing\n\n      Using token [REDACTED]\n\n  - Done (< 0.1s)\n- Done (finished in < 0.1s)\n",
+//!     bullet_stream::strip_ansi(String::from_utf8_lossy(&output))
+//! );
+//! ```
+//!
+//! Secrets are matched against the visible text, so one that is split by a change of color is
+//! still found, and escape sequences are kept so colors don't bleed into the rest of the output.
+//! Streamed output is matched a whole line at a time, so a secret that is written in several
+//! pieces is still found. Secrets that span more than one line are not.
+use crate::ansi_escape::escape_sequences;
+use crate::event::Event;
+use std::fmt::Display;
+use std::ops::Range;
+
+/// Replaces each secret
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Literal secrets and patterns to hide from output.
+///
+/// Patterns use the syntax of the [`regex_lite`](https://docs.rs/regex-lite) crate, which is the
+/// same as [`regex`](https://docs.rs/regex) without Unicode classes.
+#[derive(Debug, Clone, Default)]
+pub struct Redactions {
+    secrets: Vec<String>,
+    patterns: Vec<regex_lite::Regex>,
+}
+
+impl Redactions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hide every occurrence of `secret`, empty secrets are ignored
+    #[must_use]
+    pub fn secret(mut self, secret: impl Into<String>) -> Self {
+        let secret = secret.into();
+        if !secret.is_empty() {
+            self.secrets.push(secret);
+        }
+        self
+    }
+
+    /// Hide every occurrence of each of the `secrets`, empty secrets are ignored
+    #[must_use]
+    pub fn secrets(self, secrets: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        secrets.into_iter().fold(self, Self::secret)
+    }
+
+    /// Hide every match of a regular expression
+    ///
+    /// # Errors
+    ///
+    /// When the pattern is not a valid regular expression
+    pub fn pattern(mut self, pattern: impl AsRef<str>) -> Result<Self, InvalidPattern> {
+        let pattern = pattern.as_ref();
+        let regex = regex_lite::Regex::new(pattern).map_err(|error| InvalidPattern {
+            pattern: pattern.to_string(),
+            reason: error.to_string(),
+        })?;
+        self.patterns.push(regex);
+        Ok(self)
+    }
+
+    /// True when nothing would be redacted
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.secrets.is_empty() && self.patterns.is_empty()
+    }
+
+    /// Replace every secret and match in `text` with [`REDACTED`]
+    ///
+    /// Overlapping matches are replaced once. Escape sequences are ignored while matching and
+    /// kept in the result.
+    #[must_use]
+    pub fn redact(&self, text: &str) -> String {
+        let sequences = escape_sequences(text);
+
+        // Visible text, along with the position in `text` of each of its bytes
+        let mut visible = String::with_capacity(text.len());
+        let mut offsets = Vec::with_capacity(text.len());
+        let mut last = 0;
+        for sequence in sequences
+            .iter()
+            .chain(std::iter::once(&(text.len()..text.len())))
+        {
+            visible.push_str(&text[last..sequence.start]);
+            offsets.extend(last..sequence.start);
+            last = sequence.end;
+        }
+
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        for range in self.matches(&visible) {
+            let start = offsets[range.start];
+            let end = offsets[range.end - 1] + 1;
+            result.push_str(&text[last..start]);
+            result.push_str(REDACTED);
+            for sequence in sequences
+                .iter()
+                .filter(|sequence| start <= sequence.start && sequence.end <= end)
+            {
+                result.push_str(&text[sequence.clone()]);
+            }
+            last = end;
+        }
+        result.push_str(&text[last..]);
+        result
+    }
+
+    /// Redact the text of an event
+    pub(crate) fn event(&self, event: Event) -> Event {
+        if self.is_empty() {
+            event
+        } else {
+            event.map_text(|text| self.redact(&text))
+        }
+    }
+
+    /// Redact a line of streamed output, lines without a match are returned unchanged
+    pub(crate) fn line(&self, line: Vec<u8>) -> Vec<u8> {
+        if self.is_empty() {
+            return line;
+        }
+        let text = String::from_utf8_lossy(&line);
+        let redacted = self.redact(&text);
+        if redacted == text {
+            line
+        } else {
+            redacted.into_bytes()
+        }
+    }
+
+    /// Byte ranges to replace, sorted and merged so none overlap
+    fn matches(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges = self
+            .secrets
+            .iter()
+            .flat_map(|secret| {
+                text.match_indices(secret.as_str())
+                    .map(|(start, found)| start..start + found.len())
+            })
+            .chain(self.patterns.iter().flat_map(|regex| {
+                regex
+                    .find_iter(text)
+                    .filter(|found| !found.is_empty())
+                    .map(|found| found.range())
+            }))
+            .collect::<Vec<_>>();
+        ranges.sort_by_key(|range| range.start);
+
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start < last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+}
+
+/// A pattern given to [`Redactions::pattern`] is not a valid regular expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidPattern {
+    pub pattern: String,
+    pub reason: String,
+}
+
+impl Display for InvalidPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid redaction pattern {:?}: {}",
+            self.pattern, self.reason
+        )
+    }
+}
+
+impl std::error::Error for InvalidPattern {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{strip_ansi, style, Print};
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+
+    #[test]
+    fn redacts_secrets_and_patterns() {
+        let redactions = Redactions::new()
+            .secrets(["hunter2", "", "hunter"])
+            .pattern(r"https://\S+\?sig=\S+")
+            .unwrap();
+        assert_eq!(
+            "pass [REDACTED] and [REDACTED], url [REDACTED] done",
+            redactions.redact("pass hunter2 and hunter, url https://example.com/a?sig=abc done")
+        );
+        assert_eq!("nothing here", redactions.redact("nothing here"));
+        assert_eq!("unchanged", Redactions::new().redact("unchanged"));
+    }
+
+    #[test]
+    fn keeps_escape_sequences() {
+        let redactions = Redactions::new()
+            .secret("hunter2")
+            .pattern(r"https://\S+")
+            .unwrap();
+
+        let url = style::url("https://example.com/a?sig=abc");
+        let actual = redactions.redact(&format!("see {url} now"));
+        assert_eq!(format!("see {} now", style::url(REDACTED)), actual);
+
+        let split = format!("hunt{}", style::url("er2"));
+        let actual = redactions.redact(&format!("pass {split} done"));
+        assert_eq!("pass [REDACTED] done", strip_ansi(&actual));
+        assert_eq!(
+            split.matches('\x1B').count(),
+            actual.matches('\x1B').count()
+        );
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let error = Redactions::new().pattern("(unclosed").unwrap_err();
+        assert_eq!("(unclosed", error.pattern);
+    }
+
+    #[test]
+    fn secrets_split_across_writes() {
+        let mut stream = Print::new(Vec::new())
+            .with_redactions(Redactions::new().secret("hunter2"))
+            .without_header()
+            .bullet("Login")
+            .start_stream("Streaming");
+        for chunk in ["pass: hun", "te", "r2\nagain hunt", "er2"] {
+            stream.write_all(chunk.as_bytes()).unwrap();
+        }
+        let output = strip_ansi(String::from_utf8_lossy(&stream.done().done().done()));
+        assert!(
+            output.contains("      pass: [REDACTED]\n      again [REDACTED]\n"),
+            "{output}"
+        );
+
+        let mut bullet = Print::new(Vec::new())
+            .with_redactions(Redactions::new().secret("hunter2"))
+            .without_header()
+            .bullet("Login");
+        bullet.stream_with("Streaming", |mut stdout, mut stderr| {
+            stdout.write_all(b"out: hunt").unwrap();
+            stderr.write_all(b"err: hu").unwrap();
+            stdout.write_all(b"er2\n").unwrap();
+            stderr.write_all(b"nter2\n").unwrap();
+        });
+        let output = strip_ansi(String::from_utf8_lossy(&bullet.done().done()));
+        assert!(output.contains("      out: [REDACTED]\n"), "{output}");
+        assert!(output.contains("      err: [REDACTED]\n"), "{output}");
+        assert!(!output.contains("hunter2"), "{output}");
+    }
+}
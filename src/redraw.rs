@@ -0,0 +1,230 @@
+//! Collapse lines that a command redraws in place with a carriage return
+use std::io::{self, Write};
+use std::mem;
+use std::time::{Duration, Instant};
+
+/// Keep streamed output small when a command redraws a line in place, such as the progress bars
+/// of `curl`, `pip` or `cargo`.
+///
+/// A carriage return (`\r`) that is not part of a `\r\n` line ending starts drawing the line again.
+/// Without a filter every redraw ends up on a single line of the log. Each part between carriage
+/// returns is treated as the whole line at that moment, and the filter applies before streamed
+/// lines are indented.
+///
+/// Use with [`crate::Print::with_redraws`] or [`crate::global::set_redraws`].
+///
+/// ```
+/// use bullet_stream::{Print, Redraws};
+/// use std::io::Write;
+///
+/// let mut stream = Print::new(Vec::new())
+///     .with_redraws(Redraws::Final)
+///     .without_header()
+///     .bullet("Downloading")
+///     .start_stream("Fetching");
+/// write!(stream, "10%\r50%\r100%\nsaved\n").unwrap();
+///
+/// let output = stream.done().done().done();
+/// assert!(bullet_stream::strip_ansi(String::from_utf8_lossy(&output))
+///     .contains("\n      100%\n      saved\n"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Redraws {
+    /// Keep only the final state of each line
+    Final,
+    /// Keep the final state of each line, along with a snapshot while it's being redrawn at most
+    /// once per interval. The interval starts over with every line that is written.
+    Throttle(Duration),
+}
+
+/// Collapses redraws of the bytes written to it before passing whole lines to `inner`
+#[derive(Debug)]
+pub(crate) struct RedrawWrite<W: Write> {
+    // Wrapped in an `Option` to support `unwrap` along with `Drop`, see `MappedWrite`
+    inner: Option<W>,
+    collapse: Option<Collapse>,
+}
+
+impl<W: Write> RedrawWrite<W> {
+    /// Passes bytes through unchanged when `redraws` is `None`
+    pub(crate) fn new(inner: W, redraws: Option<Redraws>) -> Self {
+        Self {
+            inner: Some(inner),
+            collapse: redraws.map(Collapse::new),
+        }
+    }
+
+    pub(crate) fn unwrap(mut self) -> W {
+        let _result = self.write_rest();
+        self.inner
+            .take()
+            .expect("self.inner is only None after unwrap")
+    }
+
+    /// The rest of a line that was never ended is its final state
+    fn write_rest(&mut self) -> io::Result<()> {
+        match (&mut self.inner, &mut self.collapse) {
+            (Some(inner), Some(collapse)) => inner.write_all(&collapse.finish()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Write for RedrawWrite<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let inner = self
+            .inner
+            .as_mut()
+            .expect("self.inner is only None after unwrap");
+        match &mut self.collapse {
+            Some(collapse) => {
+                inner.write_all(&collapse.push(buf, Instant::now()))?;
+                Ok(buf.len())
+            }
+            None => inner.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            Some(inner) => inner.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Drop for RedrawWrite<W> {
+    fn drop(&mut self) {
+        // Drop implementations must not panic. We intentionally ignore the potential error here.
+        let _result = self.write_rest();
+    }
+}
+
+/// Tracks the line being redrawn, state is preserved between calls so a `\r\n` split across two
+/// writes is still a line ending
+#[derive(Debug, Clone)]
+pub(crate) struct Collapse {
+    redraws: Redraws,
+    line: Vec<u8>,
+    carriage_return: bool,
+    last_written: Instant,
+}
+
+impl Collapse {
+    pub(crate) fn new(redraws: Redraws) -> Self {
+        Self {
+            redraws,
+            line: Vec::new(),
+            carriage_return: false,
+            last_written: Instant::now(),
+        }
+    }
+
+    /// Returns whole lines to write, the line being drawn is held back
+    pub(crate) fn push(&mut self, buf: &[u8], now: Instant) -> Vec<u8> {
+        let mut result = Vec::new();
+        for &byte in buf {
+            if mem::take(&mut self.carriage_return) {
+                if byte == b'\n' {
+                    self.line.push(b'\r');
+                } else {
+                    self.redrawn(now, &mut result);
+                }
+            }
+            match byte {
+                b'\r' => self.carriage_return = true,
+                b'\n' => {
+                    self.line.push(byte);
+                    result.append(&mut self.line);
+                    self.last_written = now;
+                }
+                _ => self.line.push(byte),
+            }
+        }
+        result
+    }
+
+    /// The line as it was last drawn
+    pub(crate) fn finish(&mut self) -> Vec<u8> {
+        self.carriage_return = false;
+        mem::take(&mut self.line)
+    }
+
+    fn redrawn(&mut self, now: Instant, result: &mut Vec<u8>) {
+        let snapshot = mem::take(&mut self.line);
+        match self.redraws {
+            Redraws::Throttle(interval)
+                if !snapshot.is_empty() && now.duration_since(self.last_written) >= interval =>
+            {
+                result.extend(snapshot);
+                result.push(b'\n');
+                self.last_written = now;
+            }
+            Redraws::Throttle(_) | Redraws::Final => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn collapse(redraws: Redraws, writes: &[(&str, u64)]) -> String {
+        let started = Instant::now();
+        let mut collapse = Collapse::new(redraws);
+        collapse.last_written = started;
+        let mut output = Vec::new();
+        for (write, at) in writes {
+            output.extend(collapse.push(write.as_bytes(), started + Duration::from_secs(*at)));
+        }
+        output.extend(collapse.finish());
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn final_state() {
+        assert_eq!(
+            "100%\nline\r\nplain\nlast",
+            collapse(
+                Redraws::Final,
+                &[
+                    ("\r10%\r5", 0),
+                    ("0%\r100%\nline\r", 1),
+                    ("\nplain\nlas\rlast", 2)
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn throttled_snapshots() {
+        let writes = [
+            ("10%\r", 0),
+            ("20%\r", 1),
+            ("30%\r", 2),
+            ("40%\r", 3),
+            ("100%\nnext\r", 4),
+            ("done\n", 5),
+        ];
+        assert_eq!(
+            "20%\n40%\n100%\ndone\n",
+            collapse(Redraws::Throttle(Duration::from_secs(2)), &writes)
+        );
+        assert_eq!(
+            "10%\n20%\n30%\n40%\n100%\nnext\ndone\n",
+            collapse(Redraws::Throttle(Duration::ZERO), &writes)
+        );
+    }
+
+    #[test]
+    fn writer_passes_through_without_redraws() {
+        let mut write = RedrawWrite::new(Vec::new(), None);
+        write.write_all(b"10%\r100%\n").unwrap();
+        assert_eq!(b"10%\r100%\n".to_vec(), write.unwrap());
+
+        let mut write = RedrawWrite::new(Vec::new(), Some(Redraws::Final));
+        write.write_all(b"10%\r100%\nrest\r").unwrap();
+        assert_eq!(b"100%\nrest".to_vec(), write.unwrap());
+    }
+}
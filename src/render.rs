@@ -0,0 +1,394 @@
+//! Control the format of the output
+//!
+//! The [`crate::Print`] state machine decides *what* can be written and in which order, a
+//! [`Renderer`] decides what it looks like. The default [`TextRenderer`] produces the bullet
+//! point format this library is named after.
+//!
+//! Use [`crate::Print::with_renderer`] or [`crate::global::set_renderer`] to change it:
+//!
+//! ```
+//! use bullet_stream::{event::Event, render::{Output, Renderer}, ColorChoice, Print};
+//! use std::io::Write;
+//!
+//! /// Writes only headers and bullets, without any decoration
+//! #[derive(Debug)]
+//! struct Outline;
+//!
+//! impl Renderer for Outline {
+//!     fn render(&self, out: &mut Output<'_>, event: &Event) {
+//!         match event {
+//!             Event::H2(text) | Event::Bullet(text) => writeln!(out, "{text}").unwrap(),
+//!             Event::SubBullet(text) => writeln!(out, "\t{text}").unwrap(),
+//!             _ => {}
+//!         }
+//!     }
+//! }
+//!
+//! let output = Print::new(Vec::new())
+//!     .with_renderer(Outline)
+//!     .h2("Example Buildpack")
+//!     .bullet("Ruby version")
+//!     .sub_bullet("Using 3.4.1")
+//!     .done()
+//!     .done();
+//!
+//! assert_eq!(
+//!     "Example Buildpack\nRuby version\n\tUsing 3.4.1\n",
+//!     String::from_utf8_lossy(&output)
+//! );
+//! ```
+use crate::ansi_escape::{self, ANSI};
+use crate::duration_format;
+use crate::event::Event;
+use crate::style::{self, Theme, CMD_INDENT};
+use crate::text_wrap;
+use crate::util::{prefix_first_rest_lines, prefix_lines, Config, TrailingParagraph};
+use std::fmt::Debug;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Turns [`Event`]s into bytes.
+///
+/// Implementations must be cheap to share between threads as streamed output and timers
+/// are written from background threads.
+pub trait Renderer: Debug + Send + Sync {
+    /// Write a single event to the output
+    fn render(&self, out: &mut Output<'_>, event: &Event);
+
+    /// Map a single line of streamed output, such as from a command, including the trailing newline
+    ///
+    /// The last line of a stream may not end in a newline and can be empty. Streamed output
+    /// is written through this function, [`Event::StreamOutput`] is only sent to event sinks.
+    fn stream_line(&self, line: Vec<u8>) -> Vec<u8> {
+        line
+    }
+
+    /// Text written by a timer while it's running in the background
+    fn timer_ticks(&self, theme: &Theme) -> TimerTicks {
+        TextRenderer.timer_ticks(theme)
+    }
+}
+
+/// Text emitted by a background timer.
+///
+/// The `start` is written once after the [`Event::TimerStart`] is rendered, followed by
+/// one `tick` per interval, and `end` when the timer is stopped. If a timer is dropped without
+/// being stopped, `on_drop` and a newline are written instead of rendering a [`Event::TimerDone`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimerTicks {
+    pub start: String,
+    pub tick: String,
+    pub end: String,
+    pub on_drop: String,
+}
+
+/// The destination of a [`Renderer`].
+pub struct Output<'a> {
+    write: &'a mut dyn TrailingParagraph,
+    config: Config,
+}
+
+impl<'a> Output<'a> {
+    pub(crate) fn new(write: &'a mut dyn TrailingParagraph, config: Config) -> Self {
+        Self { write, config }
+    }
+
+    /// True if the last thing written was an empty line
+    #[must_use]
+    pub fn trailing_paragraph(&self) -> bool {
+        self.write.trailing_paragraph()
+    }
+
+    /// Width to wrap text to, as configured with [`crate::Print::with_wrap`]
+    #[must_use]
+    pub fn width(&self) -> Option<usize> {
+        self.config.width
+    }
+
+    pub(crate) fn theme(&self) -> Arc<Theme> {
+        self.config.theme.clone()
+    }
+}
+
+impl Write for Output<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.write.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.write.flush()
+    }
+}
+
+/// Redact the event and emit it to sinks, then render it
+pub(crate) fn render<W: TrailingParagraph>(writer: &mut W, event: Event) {
+    let config = writer.config();
+    let event = config.redactions.event(event);
+    config.events.emit(event.clone());
+    let renderer = config.renderer.clone();
+    renderer.render(&mut Output::new(writer, config), &event);
+}
+
+/// The default bullet point format
+///
+/// ```text
+/// ## Example Buildpack
+///
+/// - Ruby version
+///   - Installing ... (1.2s)
+///   - Running `bundle install`
+///
+///       Bundle complete!
+///
+///   - Done (2.1s)
+/// - Done (finished in 3.3s)
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextRenderer;
+
+impl Renderer for TextRenderer {
+    fn render(&self, out: &mut Output<'_>, event: &Event) {
+        let theme = out.theme();
+        match event {
+            Event::H1(s) => header(out, &theme.h1, format!("# {}", s.trim())),
+            Event::H2(s) => header(out, &theme.h2, format!("## {}", s.trim())),
+            Event::H3(s) => header(out, &theme.h3, format!("### {}", s.trim())),
+            Event::Plain(s) => writeln!(out, "{}", s.trim_end()).expect("writer open"),
+            Event::Bullet(s) => bullet(out, s),
+            Event::BulletDone => {}
+            Event::SubBullet(s) => {
+                writeln!(out, "{}", sub_bullet_prefix(fill(out, 4, s))).expect("writer open");
+            }
+            Event::NestedBullet { depth, text } => {
+                let text = fill(out, depth * 2, text);
+                writeln!(out, "{}", nested_bullet_prefix(*depth, text)).expect("writer open");
+            }
+            Event::Warning(s) => paragraph(out, &theme.warning, s),
+            Event::Error(s) => paragraph(out, &theme.error, s),
+            Event::Important(s) => paragraph(out, &theme.important, s),
+            Event::StreamStart(s) => {
+                writeln!(out, "{}", sub_bullet_prefix(s)).expect("writer open");
+                writeln!(out).expect("writer open");
+            }
+            // Written via `stream_line`
+            Event::StreamOutput(_) => {}
+            Event::StreamDone(duration) => {
+                if !out.trailing_paragraph() {
+                    writeln!(out).expect("writer open");
+                }
+                writeln!(
+                    out,
+                    "{}",
+                    sub_bullet_prefix(format!(
+                        "Done {}",
+                        style::details(duration_format::human(duration))
+                    ))
+                )
+                .expect("writer open");
+            }
+            Event::StreamsDone { summary, .. } => {
+                if !out.trailing_paragraph() {
+                    writeln!(out).expect("writer open");
+                }
+                writeln!(
+                    out,
+                    "{}",
+                    sub_bullet_prefix(format!("Done {}", style::details(summary)))
+                )
+                .expect("writer open");
+            }
+            Event::TimerStart(s) => {
+                // Do not emit a newline after the message
+                write!(out, "{}", sub_bullet_prefix(s)).expect("writer open");
+            }
+            Event::TimerDone(duration) => {
+                writeln!(out, "{}", style::details(duration_format::human(duration)))
+                    .expect("writer open");
+            }
+            Event::TimerCancel(why) => {
+                writeln!(out, "{}", style::details(why)).expect("writer open");
+            }
+            Event::AllDone(Some(duration)) => bullet(
+                out,
+                format!("Done (finished in {})", duration_format::human(duration)),
+            ),
+            Event::AllDone(None) => bullet(out, "Done"),
+        }
+        out.flush().expect("writer open");
+    }
+
+    fn stream_line(&self, mut line: Vec<u8>) -> Vec<u8> {
+        // Avoid adding trailing whitespace to the line, if there was none already.
+        // The `[b'\n']` case is required since `line` includes the trailing newline byte.
+        if line.is_empty() || line == [b'\n'] {
+            line
+        } else {
+            let mut result: Vec<u8> = CMD_INDENT.into();
+            result.append(&mut line);
+            result
+        }
+    }
+
+    fn timer_ticks(&self, theme: &Theme) -> TimerTicks {
+        TimerTicks {
+            start: ansi_escape::wrap_ansi_escape_each_line(&theme.dim, " ."),
+            tick: ansi_escape::wrap_ansi_escape_each_line(&theme.dim, "."),
+            end: ansi_escape::wrap_ansi_escape_each_line(&theme.dim, ". "),
+            on_drop: "(Error)".to_string(),
+        }
+    }
+}
+
+fn header(out: &mut Output<'_>, color: &ANSI, s: String) {
+    if !out.trailing_paragraph() {
+        writeln!(out).expect("writer open");
+    }
+
+    writeln!(out, "{}", ansi_escape::wrap_ansi_escape_each_line(color, s)).expect("writer open");
+
+    if !out.trailing_paragraph() {
+        writeln!(out).expect("writer open");
+    }
+}
+
+fn bullet(out: &mut Output<'_>, s: impl AsRef<str>) {
+    let s = fill(out, 2, s);
+    writeln!(out, "{}", prefix_first_rest_lines("- ", "  ", s.trim())).expect("writer open");
+}
+
+/// Wrap to the width of the output, leaving room for a prefix of `indent` columns
+fn fill(out: &Output<'_>, indent: usize, s: impl AsRef<str>) -> String {
+    let s = s.as_ref().trim();
+    match out.width() {
+        Some(width) => text_wrap::wrap(s, width.saturating_sub(indent)),
+        None => s.to_string(),
+    }
+}
+
+fn sub_bullet_prefix(s: impl AsRef<str>) -> String {
+    prefix_first_rest_lines("  - ", "    ", s.as_ref().trim())
+}
+
+fn nested_bullet_prefix(depth: usize, s: impl AsRef<str>) -> String {
+    let indent = "  ".repeat(depth.saturating_sub(1));
+    prefix_first_rest_lines(
+        &format!("{indent}- "),
+        &format!("{indent}  "),
+        s.as_ref().trim(),
+    )
+}
+
+fn paragraph(out: &mut Output<'_>, color: &ANSI, s: impl AsRef<str>) {
+    let contents = fill(out, 2, s);
+    let contents = contents.as_str();
+
+    if !out.trailing_paragraph() {
+        writeln!(out).expect("writer open");
+    }
+
+    writeln!(
+        out,
+        "{}",
+        ansi_escape::wrap_ansi_escape_each_line(
+            color,
+            prefix_lines(contents, |_, line| {
+                // Avoid adding trailing whitespace to the line, if there was none already.
+                // The `\n` case is required since `prefix_lines` uses `str::split_inclusive`,
+                // which preserves any trailing newline characters if present.
+                if line.is_empty() || line == "\n" {
+                    String::from("!")
+                } else {
+                    String::from("! ")
+                }
+            }),
+        ),
+    )
+    .expect("writer open");
+    writeln!(out).expect("writer open");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Print;
+    use pretty_assertions::assert_eq;
+
+    #[derive(Debug)]
+    struct Arrows;
+
+    impl Renderer for Arrows {
+        fn render(&self, out: &mut Output<'_>, event: &Event) {
+            match event {
+                Event::Bullet(s) | Event::StreamStart(s) | Event::TimerStart(s) => {
+                    write!(out, "> {s}").unwrap();
+                }
+                Event::StreamDone(_) | Event::TimerDone(_) => writeln!(out, "<").unwrap(),
+                _ => {}
+            }
+            if matches!(event, Event::Bullet(_) | Event::StreamStart(_)) {
+                writeln!(out).unwrap();
+            }
+        }
+
+        fn stream_line(&self, line: Vec<u8>) -> Vec<u8> {
+            if line.is_empty() {
+                line
+            } else {
+                [b"| ".to_vec(), line].concat()
+            }
+        }
+
+        fn timer_ticks(&self, _theme: &Theme) -> TimerTicks {
+            TimerTicks {
+                start: String::from(" ["),
+                tick: String::from("#"),
+                end: String::from("] "),
+                on_drop: String::from("!"),
+            }
+        }
+    }
+
+    #[test]
+    fn custom_renderer_streams_and_timers() {
+        let mut stream = Print::new(Vec::new())
+            .with_renderer(Arrows)
+            .without_header()
+            .bullet("Bullet")
+            .start_stream("Stream");
+        writeln!(stream, "one\ntwo").unwrap();
+        let io = stream.done().start_timer("Timer").done().done().done();
+
+        assert_eq!(
+            "> Bullet\n> Stream\n| one\n| two\n<\n> Timer [#] <\n",
+            String::from_utf8_lossy(&io)
+        );
+    }
+
+    #[test]
+    fn wraps_with_hanging_indent() {
+        let io = Print::new(Vec::new())
+            .with_wrap(crate::Wrapping::Columns(16))
+            .without_header()
+            .bullet("Installing the dependencies")
+            .sub_bullet("Using the cached gems")
+            .nested("Skipping the gem install")
+            .done()
+            .error("Something went wrong");
+
+        assert_eq!(
+            indoc::indoc! {"
+                - Installing the
+                  dependencies
+                  - Using the
+                    cached gems
+                  - Skipping the
+                    gem install
+
+                ! Something went
+                ! wrong
+
+            "},
+            crate::strip_ansi(String::from_utf8_lossy(&io))
+        );
+    }
+}
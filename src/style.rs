@@ -1,24 +1,157 @@
 //! Helpers for formatting and colorizing your output.
 
 use crate::ansi_escape::{self, ANSI};
+use crate::global;
 pub(crate) const CMD_INDENT: &str = "      ";
 
+/// Maps each part of the output to a color.
+///
+/// Styles are ANSI SGR parameters, for example `1;35` is bold purple and `38;5;208` is orange
+/// on terminals that support 256 colors. An empty string disables styling for that part.
+/// The [`Default`] theme contains the colors used by this library.
+///
+/// Use with [`crate::Print::with_theme`] or [`crate::global::set_theme`]. The helper functions
+/// in this module such as [`value`] are formatted before they reach a writer, so they always
+/// use the theme set with [`crate::global::set_theme`].
+///
+/// ```
+/// use bullet_stream::{style::Theme, ColorChoice, Print};
+///
+/// // Don't rely on red versus yellow alone to tell errors and warnings apart
+/// let theme = Theme::default()
+///     .warning("1;33")
+///     .error("1;4;31");
+///
+/// let output = Print::new(Vec::new())
+///     .with_color(ColorChoice::Always)
+///     .with_theme(theme)
+///     .without_header()
+///     .error("Bold, underlined, and red");
+///
+/// assert_eq!(
+///     "\n\u{1b}[1;4;31m! Bold, underlined, and red\u{1b}[0m\n\n",
+///     String::from_utf8_lossy(&output)
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub(crate) h1: ANSI,
+    pub(crate) h2: ANSI,
+    pub(crate) h3: ANSI,
+    pub(crate) warning: ANSI,
+    pub(crate) error: ANSI,
+    pub(crate) important: ANSI,
+    pub(crate) url: ANSI,
+    pub(crate) value: ANSI,
+    pub(crate) command: ANSI,
+    pub(crate) dim: ANSI,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            h1: ANSI::BoldPurple,
+            h2: ANSI::BoldPurple,
+            h3: ANSI::from_sgr(""),
+            warning: ANSI::Yellow,
+            error: ANSI::Red,
+            important: ANSI::BoldCyan,
+            url: ANSI::BoldUnderlineCyan,
+            value: ANSI::Yellow,
+            command: ANSI::BoldCyan,
+            dim: ANSI::Dim,
+        }
+    }
+}
+
+impl Theme {
+    /// Style of `h1` headers. Default: bold purple (`1;35`).
+    #[must_use]
+    pub fn h1(mut self, sgr: impl AsRef<str>) -> Self {
+        self.h1 = ANSI::from_sgr(sgr);
+        self
+    }
+
+    /// Style of `h2` headers. Default: bold purple (`1;35`).
+    #[must_use]
+    pub fn h2(mut self, sgr: impl AsRef<str>) -> Self {
+        self.h2 = ANSI::from_sgr(sgr);
+        self
+    }
+
+    /// Style of `h3` headers. Default: unstyled (empty).
+    #[must_use]
+    pub fn h3(mut self, sgr: impl AsRef<str>) -> Self {
+        self.h3 = ANSI::from_sgr(sgr);
+        self
+    }
+
+    /// Style of warning paragraphs. Default: yellow (`0;33`).
+    #[must_use]
+    pub fn warning(mut self, sgr: impl AsRef<str>) -> Self {
+        self.warning = ANSI::from_sgr(sgr);
+        self
+    }
+
+    /// Style of error paragraphs. Default: red (`0;31`).
+    #[must_use]
+    pub fn error(mut self, sgr: impl AsRef<str>) -> Self {
+        self.error = ANSI::from_sgr(sgr);
+        self
+    }
+
+    /// Style of important paragraphs and [`important`] text. Default: bold cyan (`1;36`).
+    #[must_use]
+    pub fn important(mut self, sgr: impl AsRef<str>) -> Self {
+        self.important = ANSI::from_sgr(sgr);
+        self
+    }
+
+    /// Style of [`url`] text. Default: bold underlined cyan (`1;4;36`).
+    #[must_use]
+    pub fn url(mut self, sgr: impl AsRef<str>) -> Self {
+        self.url = ANSI::from_sgr(sgr);
+        self
+    }
+
+    /// Style of [`value`] text. Default: yellow (`0;33`).
+    #[must_use]
+    pub fn value(mut self, sgr: impl AsRef<str>) -> Self {
+        self.value = ANSI::from_sgr(sgr);
+        self
+    }
+
+    /// Style of [`command`] text. Default: bold cyan (`1;36`).
+    #[must_use]
+    pub fn command(mut self, sgr: impl AsRef<str>) -> Self {
+        self.command = ANSI::from_sgr(sgr);
+        self
+    }
+
+    /// Style of de-emphasized output such as timer dots. Default: dim (`2;1`).
+    #[must_use]
+    pub fn dim(mut self, sgr: impl AsRef<str>) -> Self {
+        self.dim = ANSI::from_sgr(sgr);
+        self
+    }
+}
+
 /// Decorate a URL for the build output.
 pub fn url(contents: impl AsRef<str>) -> String {
-    ansi_escape::wrap_ansi_escape_each_line(&ANSI::BoldUnderlineCyan, contents)
+    ansi_escape::wrap_ansi_escape_each_line(&global::theme().url, contents)
 }
 
 /// Decorate the name of a command being run i.e. `bundle install`.
 pub fn command(contents: impl AsRef<str>) -> String {
     value(ansi_escape::wrap_ansi_escape_each_line(
-        &ANSI::BoldCyan,
+        &global::theme().command,
         contents,
     ))
 }
 
 /// Decorate an important value i.e. `2.3.4`.
 pub fn value(contents: impl AsRef<str>) -> String {
-    let contents = ansi_escape::wrap_ansi_escape_each_line(&ANSI::Yellow, contents);
+    let contents = ansi_escape::wrap_ansi_escape_each_line(&global::theme().value, contents);
     format!("`{contents}`")
 }
 
@@ -29,7 +162,7 @@ pub fn details(contents: impl AsRef<str>) -> String {
 }
 
 /// Verb-ify command output i.e. "Running `bundle install`".
-#[cfg(feature = "fun_run")]
+#[cfg(any(feature = "fun_run", feature = "tokio"))]
 pub(crate) fn running_command(contents: impl AsRef<str>) -> String {
     format!("Running {}", command(contents))
 }
@@ -43,5 +176,5 @@ pub(crate) fn running_command(contents: impl AsRef<str>) -> String {
 /// format!("{help} review the logs");
 /// ```
 pub fn important(contents: impl AsRef<str>) -> String {
-    ansi_escape::wrap_ansi_escape_each_line(&ANSI::BoldCyan, contents)
+    ansi_escape::wrap_ansi_escape_each_line(&global::theme().important, contents)
 }
@@ -0,0 +1,190 @@
+//! Keep only the last lines of streamed output
+use crate::ansi_escape;
+use crate::event::Event;
+use crate::util::{self, Config, TrailingParagraph};
+use crate::Verbosity;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Clear from the cursor to the end of the screen
+const CLEAR_BELOW: &str = "\x1B[J";
+/// Shortest time between frames on a terminal, lines that arrive in between are drawn together
+const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+/// Width used to count wrapped rows when the size of the terminal cannot be detected
+const DEFAULT_WIDTH: usize = 80;
+
+/// A rolling window of the last lines of a stream.
+///
+/// On a terminal the window is redrawn in place under the sub-bullet as lines arrive, at most once
+/// per [`FRAME_INTERVAL`]. Otherwise
+/// nothing is written until the stream finishes, then a count of the omitted lines is written
+/// followed by the window. Every line is emitted to event sinks as it arrives.
+pub(crate) struct Tail {
+    limit: usize,
+    /// Redacted lines before they're rendered
+    window: VecDeque<Vec<u8>>,
+    omitted: usize,
+    config: Config,
+    /// Rows on the screen drawn by the last frame, `None` when not redrawing in place
+    drawn: Option<usize>,
+    last_frame: Option<Instant>,
+    /// Lines arrived since the last frame
+    pending: bool,
+}
+
+impl Tail {
+    pub(crate) fn new<W: TrailingParagraph>(writer: &W, limit: usize) -> Self {
+        let config = writer.config();
+        let redraw = config.can_redraw() && config.verbosity != Verbosity::Quiet;
+        Self {
+            limit,
+            window: VecDeque::new(),
+            omitted: 0,
+            config,
+            drawn: redraw.then_some(0),
+            last_frame: None,
+            pending: false,
+        }
+    }
+
+    pub(crate) fn push<W: TrailingParagraph>(&mut self, writer: &mut W, line: Vec<u8>) {
+        if line.is_empty() {
+            return;
+        }
+        let line = self.config.redactions.line(line);
+        self.config.events.emit(Event::StreamOutput(
+            String::from_utf8_lossy(&line).into_owned(),
+        ));
+        self.window.push_back(line);
+        if self.window.len() > self.limit {
+            self.window.pop_front();
+            self.omitted += 1;
+        }
+
+        if self.drawn.is_some() {
+            self.pending = true;
+            if self
+                .last_frame
+                .is_none_or(|at| at.elapsed() >= FRAME_INTERVAL)
+            {
+                self.draw(writer);
+            }
+        }
+    }
+
+    /// Write the window if it wasn't drawn already, `show` is false to write nothing
+    pub(crate) fn finish<W: TrailingParagraph>(mut self, writer: &mut W, show: bool) {
+        if self.drawn.is_some() {
+            if self.pending {
+                self.draw(writer);
+            }
+            return;
+        }
+        if !show {
+            return;
+        }
+        for line in self.rendered() {
+            writer.write_all(&line).expect("Writer to not be closed");
+        }
+    }
+
+    /// Replace the last frame on a terminal, counting rows that wrap at the width of the terminal
+    fn draw<W: TrailingParagraph>(&mut self, writer: &mut W) {
+        let drawn = self.drawn.unwrap_or_default();
+        let width = util::terminal_width().unwrap_or(DEFAULT_WIDTH);
+        let mut frame = Vec::new();
+        if drawn > 0 {
+            // Move to the start of the first row that was drawn
+            frame.extend(format!("\r\x1B[{drawn}A{CLEAR_BELOW}").into_bytes());
+        }
+        let mut total = 0;
+        for mut line in self.rendered() {
+            if !line.ends_with(b"\n") {
+                line.push(b'\n');
+            }
+            total += ansi_escape::rows(String::from_utf8_lossy(&line), width);
+            frame.extend(line);
+        }
+        writer.write_all(&frame).expect("Writer to not be closed");
+        writer.flush().expect("Writer to not be closed");
+        self.drawn = Some(total);
+        self.last_frame = Some(Instant::now());
+        self.pending = false;
+    }
+
+    /// The count of omitted lines followed by the window, rendered for writing
+    fn rendered(&self) -> Vec<Vec<u8>> {
+        let summary = (self.omitted > 0).then(|| {
+            let lines = if self.omitted == 1 { "line" } else { "lines" };
+            let mut summary = ansi_escape::wrap_ansi_escape_each_line(
+                &self.config.theme.dim,
+                format!("… {} {lines} omitted …", thousands(self.omitted)),
+            )
+            .into_bytes();
+            summary.push(b'\n');
+            summary
+        });
+        summary
+            .into_iter()
+            .chain(self.window.iter().flat_map(|line| {
+                if self.config.line_safe {
+                    ansi_escape::split_redraws(line)
+                } else {
+                    vec![line.clone()]
+                }
+            }))
+            .map(|line| self.config.renderer.stream_line(line))
+            .collect()
+    }
+}
+
+/// Format a count with commas between groups of three digits
+fn thousands(count: usize) -> String {
+    let digits = count.to_string();
+    let mut result = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(digit);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::ParagraphInspectWrite;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn formats_thousands() {
+        assert_eq!("0", thousands(0));
+        assert_eq!("999", thousands(999));
+        assert_eq!("1,000", thousands(1000));
+        assert_eq!("12,345", thousands(12_345));
+        assert_eq!("1,234,567", thousands(1_234_567));
+    }
+
+    #[test]
+    fn redraws_on_a_terminal() {
+        let mut write = ParagraphInspectWrite::with_terminal(Vec::new(), true);
+        let mut tail = Tail::new(&write, 2);
+        for line in 1..=1000 {
+            tail.push(&mut write, format!("{line}\n").into_bytes());
+        }
+        tail.finish(&mut write, true);
+
+        let output = crate::ansi_escape::strip_colors(String::from_utf8_lossy(&write.inner));
+        // Lines that arrive together are drawn in a single frame
+        assert_eq!(
+            "      1\n\r\x1B[1A\x1B[J      ",
+            &output[..output.find('…').unwrap()]
+        );
+        let last_frame = output.rsplit(CLEAR_BELOW).next().unwrap();
+        assert_eq!(
+            "      … 998 lines omitted …\n      999\n      1000\n",
+            last_frame
+        );
+    }
+}
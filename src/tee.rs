@@ -0,0 +1,149 @@
+//! Write the same output to several destinations
+use crate::ansi_escape::AnsiStripper;
+use crate::util;
+use crate::ColorChoice;
+use std::any::Any;
+use std::fmt::Debug;
+use std::io::Write;
+
+/// Writes output to several sinks, each with its own color setting.
+///
+/// Use it with [`crate::Print::new`] or [`crate::global::set_writer`] to keep a plain text log
+/// file next to colored output on a terminal:
+///
+/// ```no_run
+/// use bullet_stream::{ColorChoice, Print, Tee};
+///
+/// let log = std::fs::File::create("build.log").unwrap();
+/// let tee = Tee::new()
+///     .with(std::io::stderr(), ColorChoice::Auto)
+///     .with(log, ColorChoice::Never);
+///
+/// Print::new(tee)
+///     .h2("Example Buildpack")
+///     .bullet("Ruby version")
+///     .sub_bullet("Using 3.4.1")
+///     .done()
+///     .done();
+/// ```
+///
+/// Every sink receives the same output, only colors differ, so blank lines between paragraphs
+/// are the same in all of them. The color of each sink is decided by its own [`ColorChoice`],
+/// so [`crate::Print::with_color`] and [`crate::global::set_color`] have no effect. A tee is not
+/// a terminal, timers print dots instead of redrawing in place.
+#[derive(Default)]
+pub struct Tee {
+    sinks: Vec<Sink>,
+}
+
+struct Sink {
+    write: Box<dyn Write + Send + Sync>,
+    /// Present when colors are disabled
+    strip_ansi: Option<AnsiStripper>,
+}
+
+impl Tee {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sink, [`ColorChoice::Auto`] is resolved with the terminal status of `write`
+    #[must_use]
+    pub fn with<W>(mut self, write: W, choice: ColorChoice) -> Self
+    where
+        W: Write + Send + Sync + Any,
+    {
+        let strip_ansi = (!choice.enabled(util::is_terminal(&write))).then(AnsiStripper::default);
+        self.sinks.push(Sink {
+            write: Box::new(write),
+            strip_ansi,
+        });
+        self
+    }
+}
+
+impl Write for Tee {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for sink in &mut self.sinks {
+            match &mut sink.strip_ansi {
+                Some(stripper) => sink.write.write_all(&stripper.strip(buf))?,
+                None => sink.write.write_all(buf)?,
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        for sink in &mut self.sinks {
+            sink.write.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Tee {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tee")
+            .field("sinks", &self.sinks.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::LockedWriter;
+    use crate::{global, strip_ansi, Print};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sinks_get_their_own_colors() {
+        let color = LockedWriter::new(Vec::new());
+        let plain = LockedWriter::new(Vec::new());
+        Print::new(
+            Tee::new()
+                .with(color.clone(), ColorChoice::Always)
+                .with(plain.clone(), ColorChoice::Never),
+        )
+        .with_color(ColorChoice::Never)
+        .h2("Example Buildpack")
+        .warning("Careful")
+        .important("Important")
+        .bullet("Ruby")
+        .sub_bullet("Using 3.4.1")
+        .done()
+        .done();
+
+        let color = String::from_utf8(color.unwrap()).unwrap();
+        let plain = String::from_utf8(plain.unwrap()).unwrap();
+        assert!(color.contains("\x1B[0;33m! Careful\x1B[0m"), "{color:?}");
+        assert_eq!(strip_ansi(&color), plain);
+        assert!(plain.starts_with("\n## Example Buildpack\n\n! Careful\n\n! Important\n\n- Ruby\n"));
+    }
+
+    #[test]
+    fn global_tee() {
+        let color = LockedWriter::new(Vec::new());
+        let plain = LockedWriter::new(Vec::new());
+        let tee = global::with_locked_writer(
+            Tee::new()
+                .with(color.clone(), ColorChoice::Always)
+                .with(plain.clone(), ColorChoice::Never),
+            || {
+                global::set_color(ColorChoice::Never);
+                global::print::warning("Careful");
+            },
+        );
+        drop(tee);
+
+        assert_eq!(
+            "\n\x1B[0;33m! Careful\x1B[0m\n\n",
+            String::from_utf8(color.unwrap()).unwrap()
+        );
+        assert_eq!(
+            "\n! Careful\n\n",
+            String::from_utf8(plain.unwrap()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Soft-wrap text to a width without counting ANSI escape sequences
+use crate::ansi_escape::{escape_sequences, visible_width, RESET};
+use crate::util;
+
+/// Controls if bullets and paragraphs are wrapped to a width.
+///
+/// Use with [`crate::Print::with_wrap`] or [`crate::global::set_wrap`].
+///
+/// ```
+/// use bullet_stream::{Print, Wrapping};
+///
+/// let output = Print::new(Vec::new())
+///     .with_wrap(Wrapping::Columns(30))
+///     .without_header()
+///     .warning("This warning is much too long to fit on one line")
+///     .done();
+///
+/// assert_eq!(
+///     "\n! This warning is much too\n! long to fit on one line\n\n- Done (finished in < 0.1s)\n",
+///     bullet_stream::strip_ansi(String::from_utf8_lossy(&output))
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Wrapping {
+    /// Never wrap, lines are as long as their contents (default).
+    #[default]
+    Never,
+    /// Wrap to the width of the terminal when writing to one, otherwise to the `COLUMNS`
+    /// environment variable. Does not wrap when neither is known. The size of a terminal is
+    /// detected on Unix, elsewhere `COLUMNS` is always used.
+    Auto,
+    /// Wrap to a fixed number of columns.
+    Columns(usize),
+}
+
+impl Wrapping {
+    /// Resolve the choice into a width for a writer, if any
+    pub(crate) fn width(self, is_terminal: bool) -> Option<usize> {
+        match self {
+            Wrapping::Never => None,
+            Wrapping::Auto if is_terminal => util::terminal_width(),
+            Wrapping::Auto => util::columns(),
+            Wrapping::Columns(width) => Some(width),
+        }
+    }
+}
+
+/// Wrap each line of `contents` on spaces so it fits in `width` columns.
+///
+/// Words longer than the width are kept whole. When a line is broken inside of a colored
+/// span, the color is reset at the end of the line and restored at the start of the next so a
+/// prefix added to each line is not colored.
+pub(crate) fn wrap(contents: &str, width: usize) -> String {
+    contents
+        .split('\n')
+        .map(|line| wrap_line(line, width.max(1)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn wrap_line(line: &str, width: usize) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut column = 0;
+    let mut active = String::new();
+
+    for (index, word) in line.split(' ').enumerate() {
+        let word_width = visible_width(word);
+        if index == 0 {
+            result.push_str(word);
+            column = word_width;
+        } else if column + 1 + word_width > width && column > 0 && word_width > 0 {
+            if !active.is_empty() {
+                result.push_str(RESET);
+            }
+            result.push('\n');
+            result.push_str(&active);
+            result.push_str(word);
+            column = word_width;
+        } else {
+            result.push(' ');
+            result.push_str(word);
+            column += 1 + word_width;
+        }
+        track_colors(&mut active, word);
+    }
+    result
+}
+
+/// Keep the color (SGR) sequences that are in effect after `word`
+fn track_colors(active: &mut String, word: &str) {
+    for sequence in escape_sequences(word) {
+        let sequence = &word[sequence];
+        if sequence == RESET {
+            active.clear();
+        } else if sequence.starts_with("\x1B[") && sequence.ends_with('m') {
+            active.push_str(sequence);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ansi_escape::{wrap_ansi_escape_each_line, ANSI};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn wraps_on_spaces() {
+        assert_eq!("hello\nworld", wrap("hello world", 8));
+        assert_eq!("hello world", wrap("hello world", 11));
+        assert_eq!("a b\nc d", wrap("a b c d", 3));
+        assert_eq!("longword\nb", wrap("longword b", 3));
+        assert_eq!("one\n\ntwo\nthree", wrap("one\n\ntwo three", 5));
+    }
+
+    #[test]
+    fn resolves_width() {
+        assert_eq!(None, Wrapping::Never.width(true));
+        assert_eq!(Some(30), Wrapping::Columns(30).width(false));
+        // Only a terminal is measured, other writers use `COLUMNS`
+        assert_eq!(util::columns(), Wrapping::Auto.width(false));
+        assert_eq!(util::terminal_width(), Wrapping::Auto.width(true));
+    }
+
+    #[test]
+    fn escapes_do_not_count_toward_width() {
+        let colored = wrap_ansi_escape_each_line(&ANSI::Red, "hello");
+        assert_eq!(
+            format!("{colored} world"),
+            wrap(&format!("{colored} world"), 11)
+        );
+    }
+
+    #[test]
+    fn only_restores_colors() {
+        assert_eq!(
+            "\x1B[2Kmoved\ntext\nhere",
+            wrap("\x1B[2Kmoved text here", 5)
+        );
+    }
+
+    #[test]
+    fn restores_colors_across_lines() {
+        let colored = wrap_ansi_escape_each_line(&ANSI::Red, "hello big world");
+        assert_eq!(
+            "\x1B[0;31mhello big\x1B[0m\n\x1B[0;31mworld\x1B[0m",
+            wrap(&colored, 10)
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! Configure how a timer looks while it's running
+//!
+//! By default a timer started with [`crate::Print::start_timer`] prints a dot every second. Use
+//! [`TimerOptions`] with [`crate::Print::start_timer_with`] or
+//! [`crate::global::print::sub_start_timer_with`] to change it:
+//!
+//! ```
+//! use bullet_stream::{timer::TimerOptions, Print};
+//! use std::time::Duration;
+//!
+//! let timer = Print::new(std::io::stderr())
+//!     .h2("Example Buildpack")
+//!     .bullet("Ruby version")
+//!     .start_timer_with(
+//!         "Installing",
+//!         TimerOptions::default()
+//!             .interval(Duration::from_millis(250))
+//!             .spinner()
+//!             .elapsed(true),
+//!     );
+//!
+//! timer.done().done().done();
+//! ```
+use crate::ansi_escape;
+use crate::background_printer::{Tick, Wrap};
+use crate::duration_format;
+use crate::render::TimerTicks;
+use crate::style::Theme;
+use std::time::Duration;
+
+/// Shortest interval between ticks, a zero interval would write ticks nonstop
+const MIN_INTERVAL: Duration = Duration::from_millis(1);
+/// Aligns continuation lines of ticks under the text of a sub-bullet
+const CONTINUATION_INDENT: &str = "    ";
+
+/// Frames used by [`TimerOptions::spinner`]
+pub const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How a timer looks while it's running.
+///
+/// The [`Default`] prints the renderer's ticks, a dot for the default renderer, every second.
+/// Spinners and the elapsed time are redrawn in place, which is only possible on a terminal.
+/// Other writers get ticks so output stays append-only.
+///
+/// When a width is set, ticks that would go past it continue on a new line indented under the
+/// sub-bullet text, so a long running timer doesn't produce a single line with thousands of dots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimerOptions {
+    pub(crate) interval: Duration,
+    pub(crate) ticks: Option<(String, String, String)>,
+    pub(crate) spinner: Option<Vec<String>>,
+    pub(crate) elapsed: bool,
+    pub(crate) width: Option<usize>,
+}
+
+impl Default for TimerOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            ticks: None,
+            spinner: None,
+            elapsed: false,
+            width: None,
+        }
+    }
+}
+
+impl TimerOptions {
+    /// How often to print a tick or redraw. Default: one second
+    ///
+    /// Intervals shorter than one millisecond, including [`Duration::ZERO`], are raised to one
+    /// millisecond.
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval.max(MIN_INTERVAL);
+        self
+    }
+
+    /// Text written once when the timer starts, every interval, and once when it stops.
+    /// Default: `" ."`, `"."`, and `". "`
+    #[must_use]
+    pub fn ticks(
+        mut self,
+        start: impl Into<String>,
+        tick: impl Into<String>,
+        end: impl Into<String>,
+    ) -> Self {
+        self.ticks = Some((start.into(), tick.into(), end.into()));
+        self
+    }
+
+    /// Redraw a spinner in place instead of printing ticks on a terminal
+    #[must_use]
+    pub fn spinner(self) -> Self {
+        self.spinner_frames(SPINNER)
+    }
+
+    /// Redraw a spinner with custom frames in place instead of printing ticks on a terminal
+    #[must_use]
+    pub fn spinner_frames(mut self, frames: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let frames = frames.into_iter().map(Into::into).collect::<Vec<_>>();
+        self.spinner = (!frames.is_empty()).then_some(frames);
+        self
+    }
+
+    /// Show the elapsed time such as `(1m 12s)` after the ticks or spinner, updated in place
+    /// on a terminal
+    ///
+    /// A redrawn line must fit on one row, so a terminal shows a single tick before the elapsed
+    /// time instead of one per interval.
+    #[must_use]
+    pub fn elapsed(mut self, enabled: bool) -> Self {
+        self.elapsed = enabled;
+        self
+    }
+
+    /// Maximum number of columns before ticks continue on a new line, room is left for the
+    /// duration written when the timer stops. Default: the width from [`crate::Print::with_wrap`],
+    /// otherwise ticks are not wrapped
+    #[must_use]
+    pub fn width(mut self, columns: usize) -> Self {
+        self.width = Some(columns);
+        self
+    }
+
+    /// Wrap ticks that start at `column` on the current line of a writer with `width`
+    pub(crate) fn wrap(&self, column: usize, width: Option<usize>) -> Option<Wrap> {
+        self.width.or(width).map(|width| Wrap {
+            width,
+            column,
+            indent: CONTINUATION_INDENT.to_string(),
+        })
+    }
+
+    /// Apply the options to the ticks of a renderer
+    pub(crate) fn apply(&self, mut ticks: TimerTicks) -> TimerTicks {
+        if let Some((start, tick, end)) = &self.ticks {
+            ticks.start.clone_from(start);
+            ticks.tick.clone_from(tick);
+            ticks.end.clone_from(end);
+        }
+        ticks
+    }
+
+    /// What to write every interval
+    pub(crate) fn tick(&self, ticks: &TimerTicks, is_terminal: bool, theme: &Theme) -> Tick {
+        if !is_terminal || (self.spinner.is_none() && !self.elapsed) {
+            return Tick::Append(ticks.tick.clone());
+        }
+
+        let spinner = self.spinner.clone();
+        let elapsed = self.elapsed;
+        let tick = ticks.tick.clone();
+        let dim = theme.dim.clone();
+        let mut count = 0;
+        // Frames are the same width so a long timer never wraps onto a row it can't redraw
+        Tick::Redraw(Box::new(move |duration| {
+            count += 1;
+            let mut frame = match &spinner {
+                Some(frames) => ansi_escape::wrap_ansi_escape_each_line(
+                    &dim,
+                    &frames[(count - 1) % frames.len()],
+                ),
+                None => tick.clone(),
+            };
+            if elapsed {
+                frame.push_str(&ansi_escape::wrap_ansi_escape_each_line(
+                    &dim,
+                    format!(" ({})", duration_format::human(&duration)),
+                ));
+            }
+            frame
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::{Renderer, TextRenderer};
+    use crate::strip_ansi;
+    use pretty_assertions::assert_eq;
+
+    fn frames(tick: Tick, durations: &[u64]) -> Vec<String> {
+        let Tick::Redraw(mut frame) = tick else {
+            panic!("Expected a redraw, got {tick:?}")
+        };
+        durations
+            .iter()
+            .map(|secs| strip_ansi(frame(Duration::from_secs(*secs))))
+            .collect()
+    }
+
+    #[test]
+    fn ticks_when_not_a_terminal() {
+        let options = TimerOptions::default().spinner().elapsed(true);
+        let ticks = TextRenderer.timer_ticks(&Theme::default());
+        let Tick::Append(tick) = options.tick(&ticks, false, &Theme::default()) else {
+            panic!("Expected ticks to be appended")
+        };
+        assert_eq!(".", strip_ansi(tick));
+    }
+
+    #[test]
+    fn spinner_and_elapsed() {
+        let theme = Theme::default();
+        let ticks = TextRenderer.timer_ticks(&theme);
+
+        let spinner = TimerOptions::default().spinner_frames(["-", "+"]);
+        assert_eq!(
+            vec!["-", "+", "-"],
+            frames(spinner.tick(&ticks, true, &theme), &[0, 1, 2])
+        );
+
+        let elapsed = TimerOptions::default().elapsed(true);
+        assert_eq!(
+            vec![". (< 0.1s)", ". (1.0s)", ". (1h 0m 0s)"],
+            frames(elapsed.tick(&ticks, true, &theme), &[0, 1, 3600])
+        );
+    }
+
+    #[test]
+    fn wraps_long_timers() {
+        let output = crate::Print::new(Vec::new())
+            .without_header()
+            .bullet("Ruby")
+            .start_timer_with(
+                "Installing",
+                TimerOptions::default()
+                    .interval(Duration::from_millis(1))
+                    .width(20),
+            );
+        std::thread::sleep(Duration::from_millis(100));
+        let output = output.done().done().done();
+        let output = strip_ansi(String::from_utf8_lossy(&output));
+        let lines = output.lines().collect::<Vec<_>>();
+
+        assert!(lines[1].starts_with("  - Installing ....."), "{output}");
+        assert_eq!(20, lines[1].len(), "{output}");
+        assert_eq!("    ................", lines[2], "{output}");
+        let last = lines.iter().rfind(|line| line.starts_with("    ")).unwrap();
+        assert!(last.ends_with("s)"), "{output}");
+        assert!(last.contains(". ("), "{output}");
+        assert!(
+            lines[1..lines.len() - 1]
+                .iter()
+                .all(|line| line.len() <= 20),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn does_not_wrap_by_default() {
+        assert_eq!(None, TimerOptions::default().wrap(4, None));
+        assert_eq!(
+            Some(20),
+            TimerOptions::default()
+                .wrap(4, Some(20))
+                .map(|wrap| wrap.width)
+        );
+        assert_eq!(
+            Some(10),
+            TimerOptions::default()
+                .width(10)
+                .wrap(4, Some(20))
+                .map(|wrap| wrap.width)
+        );
+    }
+
+    #[test]
+    fn interval_has_a_minimum() {
+        assert_eq!(
+            MIN_INTERVAL,
+            TimerOptions::default().interval(Duration::ZERO).interval
+        );
+        assert_eq!(
+            Duration::from_millis(5),
+            TimerOptions::default()
+                .interval(Duration::from_millis(5))
+                .interval
+        );
+
+        let output = crate::Print::new(Vec::new())
+            .without_header()
+            .bullet("Ruby")
+            .start_timer_with(
+                "Installing",
+                TimerOptions::default().interval(Duration::ZERO),
+            )
+            .done()
+            .done()
+            .done();
+        assert!(strip_ansi(String::from_utf8_lossy(&output)).contains("  - Installing ."));
+    }
+
+    #[test]
+    fn custom_ticks() {
+        let ticks = TimerOptions::default()
+            .ticks(" [", "#", "] ")
+            .apply(TextRenderer.timer_ticks(&Theme::default()));
+        assert_eq!(
+            (" [", "#", "] "),
+            (
+                ticks.start.as_str(),
+                ticks.tick.as_str(),
+                ticks.end.as_str()
+            )
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Prefix streamed lines with when they were written
+use crate::ansi_escape::{self, ANSI};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Prefix each line of streamed output with when it was written.
+///
+/// Use with [`crate::Print::stream_with_timestamps`], [`crate::Print::with_timestamps`] on a
+/// stream, or [`crate::global::print::sub_stream_with_timestamps`]. The timestamp is dimmed and
+/// written before the indentation of the line.
+///
+/// ```
+/// use bullet_stream::{Print, Timestamps};
+/// use std::io::Write;
+///
+/// let mut stream = Print::new(Vec::new())
+///     .without_header()
+///     .bullet("Ruby")
+///     .start_stream("Installing")
+///     .with_timestamps(Timestamps::Elapsed);
+/// writeln!(stream, "hello").unwrap();
+///
+/// let output = stream.done().done().done();
+/// assert!(bullet_stream::strip_ansi(String::from_utf8_lossy(&output))
+///     .contains("\n00:00.000      hello\n"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timestamps {
+    /// Time since the stream started such as `01:02.345` (minutes, seconds, and milliseconds)
+    Elapsed,
+    /// Time of day in UTC such as `14:03:07.123`
+    WallClock,
+}
+
+/// Stamps lines of a single stream
+#[derive(Debug, Clone)]
+pub(crate) struct Stamp {
+    timestamps: Timestamps,
+    started: Instant,
+    dim: ANSI,
+}
+
+impl Stamp {
+    pub(crate) fn new(timestamps: Timestamps, started: Instant, dim: ANSI) -> Self {
+        Self {
+            timestamps,
+            started,
+            dim,
+        }
+    }
+
+    /// Prefix a line that was already rendered, empty lines are left alone
+    pub(crate) fn line(&self, line: Vec<u8>) -> Vec<u8> {
+        if line.is_empty() || line == [b'\n'] {
+            return line;
+        }
+        let now = match self.timestamps {
+            Timestamps::Elapsed => elapsed(self.started.elapsed()),
+            Timestamps::WallClock => wall_clock(SystemTime::now()),
+        };
+        let mut result: Vec<u8> = ansi_escape::wrap_ansi_escape_each_line(&self.dim, now).into();
+        result.extend(line);
+        result
+    }
+}
+
+fn elapsed(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    format!(
+        "{:02}:{:02}.{:03}",
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+fn wall_clock(at: SystemTime) -> String {
+    let millis = at
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000 % 24,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::strip_ansi;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn formats() {
+        assert_eq!("00:00.000", elapsed(Duration::ZERO));
+        assert_eq!("01:02.345", elapsed(Duration::from_millis(62_345)));
+        assert_eq!("120:00.000", elapsed(Duration::from_secs(7200)));
+        assert_eq!(
+            "14:03:07.123",
+            wall_clock(UNIX_EPOCH + Duration::from_millis(1_699_920_000_000 + 50_587_123))
+        );
+    }
+
+    #[test]
+    fn skips_empty_lines() {
+        let stamp = Stamp::new(Timestamps::Elapsed, Instant::now(), ANSI::Dim);
+        assert_eq!(b"\n".to_vec(), stamp.line(b"\n".to_vec()));
+        assert_eq!(Vec::<u8>::new(), stamp.line(Vec::new()));
+        assert_eq!(
+            "00:00.000      hi\n",
+            strip_ansi(String::from_utf8_lossy(&stamp.line(b"      hi\n".to_vec())))
+        );
+    }
+}
@@ -1,12 +1,20 @@
 use std::any::{Any, TypeId};
 use std::fmt::Debug;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 use std::sync::mpsc;
+use std::sync::Arc;
 #[cfg(test)]
-use std::sync::{Arc, Mutex};
+use std::sync::Mutex;
 use std::thread;
 
-use crate::style::CMD_INDENT;
+use crate::ansi_escape::{AnsiStripper, ColorChoice, LineSafe, VisibleColumn};
+use crate::ci::{Ci, CiRenderer};
+use crate::event::EventSinks;
+use crate::redact::Redactions;
+use crate::redraw::Redraws;
+use crate::render::{Renderer, TextRenderer};
+use crate::style::Theme;
+use crate::verbosity::Verbosity;
 use crate::write::line_mapped;
 
 /// Applies a prefix to the first line and a different prefix to the rest of the lines.
@@ -54,6 +62,73 @@ pub(crate) fn prefix_lines<F: Fn(usize, &str) -> String>(contents: &str, f: F) -
     }
 }
 
+/// How output is configured, it follows a writer from state to state
+///
+/// Cheap to clone. The global writer hands out a copy, so reading it only locks the writer once.
+#[derive(Debug, Clone)]
+pub(crate) struct Config {
+    /// The writer is a terminal
+    pub(crate) is_terminal: bool,
+    /// Every line must stand on its own, see [`LineSafe`]
+    pub(crate) line_safe: bool,
+    /// How lines redrawn by streamed commands are collapsed, if at all
+    pub(crate) redraws: Option<Redraws>,
+    pub(crate) verbosity: Verbosity,
+    pub(crate) theme: Arc<Theme>,
+    pub(crate) events: EventSinks,
+    /// Formats output for this writer, wrapped for [`Config::ci`] when set
+    pub(crate) renderer: Arc<dyn Renderer>,
+    /// The renderer before it's wrapped for a CI provider
+    base_renderer: Arc<dyn Renderer>,
+    ci: Option<Ci>,
+    /// Width to wrap bullets and paragraphs to
+    pub(crate) width: Option<usize>,
+    pub(crate) redactions: Arc<Redactions>,
+}
+
+impl Config {
+    pub(crate) fn new(is_terminal: bool) -> Self {
+        Self {
+            is_terminal,
+            line_safe: false,
+            redraws: None,
+            verbosity: Verbosity::default(),
+            theme: Arc::default(),
+            events: EventSinks::default(),
+            renderer: Arc::new(TextRenderer),
+            base_renderer: Arc::new(TextRenderer),
+            ci: None,
+            width: None,
+            redactions: Arc::default(),
+        }
+    }
+
+    /// True if output can be redrawn in place
+    pub(crate) fn can_redraw(&self) -> bool {
+        self.is_terminal && !self.line_safe
+    }
+
+    /// Format output with `renderer`, keeps grouping for a CI provider if one is set
+    pub(crate) fn set_renderer(&mut self, renderer: Arc<dyn Renderer>) {
+        self.base_renderer = renderer;
+        self.wrap_renderer();
+    }
+
+    /// Group output for a CI provider, or stop grouping it with `None`
+    pub(crate) fn set_ci(&mut self, ci: Option<Ci>) {
+        self.ci = ci;
+        self.wrap_renderer();
+    }
+
+    fn wrap_renderer(&mut self) {
+        let base = self.base_renderer.clone();
+        self.renderer = match self.ci {
+            Some(ci) => Arc::new(CiRenderer::new(ci, base)),
+            None => base,
+        };
+    }
+}
+
 /// A trailing newline aware writer.
 ///
 /// A paragraph style block of text has an empty newline before and after the text.
@@ -62,11 +137,22 @@ pub(crate) fn prefix_lines<F: Fn(usize, &str) -> String>(contents: &str, f: F) -
 ///
 /// This writer seeks to solve that problem by preserving knowledge of prior newline writes and
 /// exposing that information to the caller.
+///
+/// It is also the single place where output is configured for a writer, for example
+/// when colors are disabled ANSI escape sequences are removed before reaching `inner`.
 #[derive(Debug)]
 pub(crate) struct ParagraphInspectWrite<W> {
     pub(crate) inner: W,
     pub(crate) was_paragraph: bool,
     pub(crate) newlines_since_last_char: usize,
+    pub(crate) config: Config,
+    /// Present when colors are disabled
+    pub(crate) strip_ansi: Option<AnsiStripper>,
+    /// The inner writer decides on colors itself, such as a [`crate::Tee`]
+    pub(crate) colors_by_writer: bool,
+    /// Colors carried across writes when [`Config::line_safe`] is set
+    pub(crate) line_safe: LineSafe,
+    pub(crate) column: VisibleColumn,
 }
 
 pub(crate) trait TrailingParagraph: Write {
@@ -74,6 +160,12 @@ pub(crate) trait TrailingParagraph: Write {
     fn trailing_paragraph(&self) -> bool;
 
     fn trailing_newline_count(&self) -> usize;
+
+    /// Number of visible characters written since the last newline
+    fn column(&self) -> usize;
+
+    /// A copy of how output is configured
+    fn config(&self) -> Config;
 }
 
 pub(crate) trait TrailingParagraphSend: TrailingParagraph + Any + Send {}
@@ -90,16 +182,50 @@ where
     fn trailing_newline_count(&self) -> usize {
         self.newlines_since_last_char
     }
+
+    fn column(&self) -> usize {
+        self.column.get()
+    }
+
+    fn config(&self) -> Config {
+        self.config.clone()
+    }
 }
 
 impl<W> ParagraphInspectWrite<W> {
-    pub(crate) fn new(io: W) -> Self {
+    pub(crate) fn new(io: W) -> Self
+    where
+        W: Any,
+    {
+        let is_terminal = is_terminal(&io);
+        let colors_by_writer = colors_by_writer(&io);
+        let mut write = Self::with_terminal(io, is_terminal);
+        write.colors_by_writer = colors_by_writer;
+        write.set_color(ColorChoice::Auto);
+        write
+    }
+
+    /// Used when the terminal status cannot be detected from `W`, such as a boxed writer
+    pub(crate) fn with_terminal(io: W, is_terminal: bool) -> Self {
         Self {
             inner: io,
             newlines_since_last_char: 0,
             was_paragraph: false,
+            config: Config::new(is_terminal),
+            strip_ansi: None,
+            colors_by_writer: false,
+            line_safe: LineSafe::default(),
+            column: VisibleColumn::default(),
         }
     }
+
+    pub(crate) fn set_color(&mut self, choice: ColorChoice) {
+        self.strip_ansi = if self.colors_by_writer || choice.enabled(self.config.is_terminal) {
+            None
+        } else {
+            Some(AnsiStripper::colors())
+        };
+    }
 }
 
 impl<W: Write> Write for ParagraphInspectWrite<W> {
@@ -115,7 +241,22 @@ impl<W: Write> Write for ParagraphInspectWrite<W> {
         }
 
         self.was_paragraph = self.newlines_since_last_char > 1;
-        self.inner.write(buf)
+        if self.strip_ansi.is_none() && !self.config.line_safe {
+            let n = self.inner.write(buf)?;
+            self.column.update(&buf[..n]);
+            return Ok(n);
+        }
+
+        let normalized = self.config.line_safe.then(|| self.line_safe.normalize(buf));
+        let bytes = normalized.as_deref().unwrap_or(buf);
+        let stripped = self
+            .strip_ansi
+            .as_mut()
+            .map(|stripper| stripper.strip(bytes));
+        let bytes = stripped.as_deref().unwrap_or(bytes);
+        self.inner.write_all(bytes)?;
+        self.column.update(bytes);
+        Ok(buf.len())
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
@@ -123,6 +264,85 @@ impl<W: Write> Write for ParagraphInspectWrite<W> {
     }
 }
 
+/// Width of the terminal connected to stderr, stdout, or stdin, otherwise from the `COLUMNS`
+/// environment variable. Call it again to pick up a resized window.
+pub(crate) fn terminal_width() -> Option<usize> {
+    window_width().or_else(columns)
+}
+
+/// Width from the `COLUMNS` environment variable, most shells don't export it to child processes
+pub(crate) fn columns() -> Option<usize> {
+    parse_width(std::env::var("COLUMNS").ok())
+}
+
+#[cfg(unix)]
+fn window_width() -> Option<usize> {
+    [libc::STDERR_FILENO, libc::STDOUT_FILENO, libc::STDIN_FILENO]
+        .into_iter()
+        .find_map(|fd| {
+            let mut size = libc::winsize {
+                ws_row: 0,
+                ws_col: 0,
+                ws_xpixel: 0,
+                ws_ypixel: 0,
+            };
+            // SAFETY: `TIOCGWINSZ` writes a `winsize` to the pointer, which is valid for the call.
+            // A file descriptor that is not a terminal returns an error instead.
+            let result = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) };
+            (result == 0 && size.ws_col > 0).then_some(usize::from(size.ws_col))
+        })
+}
+
+#[cfg(not(unix))]
+fn window_width() -> Option<usize> {
+    None
+}
+
+fn parse_width(value: Option<String>) -> Option<usize> {
+    value
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|width| *width > 0)
+}
+
+/// Detects if a writer is an interactive terminal (TTY).
+///
+/// Generic writers cannot be inspected, so only the standard library types that
+/// implement [`IsTerminal`] are detected. Everything else is treated as a non-terminal.
+pub(crate) fn is_terminal<W: Any>(io: &W) -> bool {
+    let io = io as &dyn Any;
+    if let Some(io) = io.downcast_ref::<std::io::Stderr>() {
+        io.is_terminal()
+    } else if let Some(io) = io.downcast_ref::<std::io::Stdout>() {
+        io.is_terminal()
+    } else if let Some(io) = io.downcast_ref::<std::io::StderrLock<'static>>() {
+        io.is_terminal()
+    } else if let Some(io) = io.downcast_ref::<std::io::StdoutLock<'static>>() {
+        io.is_terminal()
+    } else if let Some(io) = io.downcast_ref::<std::fs::File>() {
+        io.is_terminal()
+    } else {
+        false
+    }
+}
+
+/// True when the writer decides on colors itself, such as a [`crate::Tee`] with a color setting
+/// for each of its sinks
+pub(crate) fn colors_by_writer<W: Any>(io: &W) -> bool {
+    (io as &dyn Any).is::<crate::Tee>()
+}
+
+/// Look up environment variables from a fixed list instead of the process environment
+#[cfg(test)]
+pub(crate) fn fake_env(
+    vars: &'static [(&'static str, &'static str)],
+) -> impl Fn(&str) -> Option<String> {
+    move |key| {
+        vars.iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.to_string())
+    }
+}
+
 #[cfg(test)]
 #[derive(Debug)]
 pub(crate) struct LockedWriter<W> {
@@ -250,27 +470,26 @@ where
     })
 }
 
+/// Buffers output so each message sent is a single whole line
 pub(crate) fn format_stream_writer<S>(stream_to: S) -> crate::write::MappedWrite<S>
 where
     S: Write + Send + Sync,
 {
-    line_mapped(stream_to, |mut line| {
-        // Avoid adding trailing whitespace to the line, if there was none already.
-        // The `[b'\n']` case is required since `line` includes the trailing newline byte.
-        if line.is_empty() || line == [b'\n'] {
-            line
-        } else {
-            let mut result: Vec<u8> = CMD_INDENT.into();
-            result.append(&mut line);
-            result
-        }
-    })
+    line_mapped(stream_to, |line| line)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn parse_terminal_width() {
+        assert_eq!(None, parse_width(None));
+        assert_eq!(None, parse_width(Some(String::from("0"))));
+        assert_eq!(None, parse_width(Some(String::from("wide"))));
+        assert_eq!(Some(120), parse_width(Some(String::from("120\n"))));
+    }
+
     #[test]
     #[allow(clippy::write_with_newline)]
     fn test_paragraph_inspect_write() {
@@ -308,6 +527,27 @@ mod test {
         assert!(inspect_write.was_paragraph);
     }
 
+    #[test]
+    fn test_paragraph_inspect_write_without_color() {
+        let mut inspect_write = ParagraphInspectWrite::new(Vec::new());
+        inspect_write.set_color(ColorChoice::Never);
+
+        write!(&mut inspect_write, "\x1B[0;31mHello\x1B[").unwrap();
+        write!(&mut inspect_write, "0m\n\n").unwrap();
+        assert!(inspect_write.was_paragraph);
+
+        assert_eq!("Hello\n\n", String::from_utf8_lossy(&inspect_write.inner));
+    }
+
+    #[test]
+    fn test_is_terminal() {
+        assert!(!is_terminal(&Vec::<u8>::new()));
+        assert_eq!(
+            std::io::stderr().is_terminal(),
+            is_terminal(&std::io::stderr())
+        );
+    }
+
     #[test]
     fn test_prefix_first_rest_lines() {
         assert_eq!("- hello", &prefix_first_rest_lines("- ", "  ", "hello"));
@@ -0,0 +1,108 @@
+//! How much detail to print
+use std::fmt::Display;
+
+/// How much detail is printed, from least to most.
+///
+/// Use with [`crate::Print::with_verbosity`] or [`crate::global::set_verbosity`]. Warnings and
+/// errors are always printed, the other levels change:
+///
+/// - Streamed command output is hidden at [`Verbosity::Quiet`]. Output of a command that fails
+///   is printed once the command finishes, so the failure can still be debugged.
+/// - Sub-bullets printed with [`crate::Print::sub_bullet_at`] are hidden until the verbosity
+///   reaches their level, such as [`Verbosity::Debug`] for debugging information.
+///
+/// ```
+/// use bullet_stream::{Print, Verbosity};
+///
+/// let output = Print::new(Vec::new())
+///     .with_verbosity(Verbosity::Normal)
+///     .without_header()
+///     .bullet("Ruby version")
+///     .sub_bullet("Using 3.4.1")
+///     .sub_bullet_at(Verbosity::Debug, "Read from Gemfile.lock")
+///     .done()
+///     .done();
+///
+/// assert_eq!(
+///     "- Ruby version\n  - Using 3.4.1\n- Done (finished in < 0.1s)\n",
+///     bullet_stream::strip_ansi(String::from_utf8_lossy(&output))
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Verbosity {
+    /// Hide streamed command output, unless the command fails
+    Quiet,
+    /// Default
+    #[default]
+    Normal,
+    /// Include sub-bullets at [`Verbosity::Verbose`]
+    Verbose,
+    /// Include everything
+    Debug,
+}
+
+impl Verbosity {
+    /// Read the verbosity from the `BP_LOG_LEVEL` environment variable.
+    ///
+    /// Values are `quiet`, `normal` (or `info`), `verbose`, and `debug` in any case. When the
+    /// variable is not set or has any other value the verbosity is [`Verbosity::Normal`].
+    ///
+    /// ```no_run
+    /// use bullet_stream::{Print, Verbosity};
+    ///
+    /// let output = Print::new(std::io::stderr())
+    ///     .with_verbosity(Verbosity::from_env())
+    ///     .h2("Example Buildpack");
+    /// ```
+    #[must_use]
+    pub fn from_env() -> Self {
+        std::env::var("BP_LOG_LEVEL")
+            .ok()
+            .and_then(|value| parse(&value))
+            .unwrap_or_default()
+    }
+}
+
+fn parse(value: &str) -> Option<Verbosity> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "quiet" => Some(Verbosity::Quiet),
+        "normal" | "info" => Some(Verbosity::Normal),
+        "verbose" => Some(Verbosity::Verbose),
+        "debug" => Some(Verbosity::Debug),
+        _ => None,
+    }
+}
+
+impl Display for Verbosity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verbosity::Quiet => write!(f, "quiet"),
+            Verbosity::Normal => write!(f, "normal"),
+            Verbosity::Verbose => write!(f, "verbose"),
+            Verbosity::Debug => write!(f, "debug"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_levels() {
+        assert_eq!(Some(Verbosity::Debug), parse("DEBUG"));
+        assert_eq!(Some(Verbosity::Quiet), parse(" quiet\n"));
+        assert_eq!(Some(Verbosity::Normal), parse("info"));
+        assert_eq!(Some(Verbosity::Verbose), parse("Verbose"));
+        assert_eq!(None, parse("loud"));
+        for level in [
+            Verbosity::Quiet,
+            Verbosity::Normal,
+            Verbosity::Verbose,
+            Verbosity::Debug,
+        ] {
+            assert_eq!(Some(level), parse(&level.to_string()));
+        }
+        assert!(Verbosity::Quiet < Verbosity::Normal && Verbosity::Verbose < Verbosity::Debug);
+    }
+}
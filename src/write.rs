@@ -1,93 +1,75 @@
-use crate::ansi_escape::ANSI;
-use crate::background_printer::PrintGuard;
+use crate::ansi_escape::{split_redraws, visible_width};
+use crate::background_printer::{PrintGuard, Tick, Wrap};
+use crate::event::Event;
+use crate::parallel::Labelled;
+use crate::redraw::RedrawWrite;
+use crate::render::{render, Renderer, TimerTicks};
+#[cfg(feature = "fun_run")]
+use crate::tail::Tail;
+use crate::timer::TimerOptions;
+use crate::timestamp::Stamp;
 use crate::util::{
-    format_stream_writer, mpsc_stream_to_output, prefix_first_rest_lines, prefix_lines,
-    ParagraphInspectWrite, TrailingParagraph, TrailingParagraphSend,
+    format_stream_writer, mpsc_stream_to_output, Config, ParagraphInspectWrite, TrailingParagraph,
+    TrailingParagraphSend,
 };
-use crate::{ansi_escape, background_printer, duration_format, state, style, Print};
+use crate::{background_printer, duration_format, state, Print, Timestamps, Verbosity};
 use std::fmt::{Debug, Formatter};
 use std::io::{self, Write};
 use std::mem;
 use std::sync::Arc;
-use std::time::Instant;
+use std::thread;
+use std::time::{Duration, Instant};
 
 pub(crate) fn h1<W: TrailingParagraph>(writer: &mut W, s: impl AsRef<str>) {
-    if !writer.trailing_paragraph() {
-        writeln!(writer).expect("writer open");
-    }
-
-    writeln!(
-        writer,
-        "{}",
-        ansi_escape::wrap_ansi_escape_each_line(
-            &ANSI::BoldPurple,
-            format!("# {}", s.as_ref().trim()),
-        ),
-    )
-    .expect("writer open");
-
-    if !writer.trailing_paragraph() {
-        writeln!(writer).expect("writer open");
-    }
-    writer.flush().expect("writer open");
+    render(writer, Event::H1(s.as_ref().to_string()));
 }
 
 pub(crate) fn h2<W: TrailingParagraph>(writer: &mut W, s: impl AsRef<str>) {
-    if !writer.trailing_paragraph() {
-        writeln!(writer).expect("writer open");
-    }
-
-    writeln!(
-        writer,
-        "{}",
-        ansi_escape::wrap_ansi_escape_each_line(
-            &ANSI::BoldPurple,
-            format!("## {}", s.as_ref().trim()),
-        ),
-    )
-    .expect("writer open");
-
-    if !writer.trailing_paragraph() {
-        writeln!(writer).expect("writer open");
-    }
-    writer.flush().expect("writer open");
+    render(writer, Event::H2(s.as_ref().to_string()));
 }
 
 pub(crate) fn h3<W: TrailingParagraph>(writer: &mut W, s: impl AsRef<str>) {
-    if !writer.trailing_paragraph() {
-        writeln!(writer).expect("writer open");
-    }
-
-    writeln!(writer, "### {}", s.as_ref().trim()).expect("writer open");
+    render(writer, Event::H3(s.as_ref().to_string()));
+}
 
-    if !writer.trailing_paragraph() {
-        writeln!(writer).expect("writer open");
-    }
-    writer.flush().expect("writer open");
+pub(crate) fn bullet<W: TrailingParagraph>(writer: &mut W, s: impl AsRef<str>) {
+    render(writer, Event::Bullet(s.as_ref().to_string()));
 }
 
-pub(crate) fn bullet<W: Write>(writer: &mut W, s: impl AsRef<str>) {
-    writeln!(
+pub(crate) fn nested_bullet<W: TrailingParagraph>(
+    writer: &mut W,
+    depth: usize,
+    s: impl AsRef<str>,
+) {
+    render(
         writer,
-        "{}",
-        prefix_first_rest_lines("- ", "  ", s.as_ref().trim())
-    )
-    .expect("writer open");
-    writer.flush().expect("writer open");
+        Event::NestedBullet {
+            depth,
+            text: s.as_ref().to_string(),
+        },
+    );
+}
+
+pub(crate) fn bullet_done<W: TrailingParagraph>(writer: &mut W) {
+    render(writer, Event::BulletDone);
 }
 
-pub(crate) fn plain<W: Write>(writer: &mut W, s: impl AsRef<str>) {
-    writeln!(writer, "{}", s.as_ref().trim_end()).expect("writer open");
-    writer.flush().expect("writer open");
+pub(crate) fn plain<W: TrailingParagraph>(writer: &mut W, s: impl AsRef<str>) {
+    render(writer, Event::Plain(s.as_ref().to_string()));
 }
 
-pub(crate) fn sub_bullet<W: Write>(writer: &mut W, s: impl AsRef<str>) {
-    writeln!(writer, "{}", sub_bullet_prefix(s)).expect("writer open");
-    writer.flush().expect("writer open");
+pub(crate) fn sub_bullet<W: TrailingParagraph>(writer: &mut W, s: impl AsRef<str>) {
+    render(writer, Event::SubBullet(s.as_ref().to_string()));
 }
 
-pub(crate) fn sub_bullet_prefix(s: impl AsRef<str>) -> String {
-    prefix_first_rest_lines("  - ", "    ", s.as_ref().trim())
+pub(crate) fn sub_bullet_at<W: TrailingParagraph>(
+    writer: &mut W,
+    level: Verbosity,
+    s: impl AsRef<str>,
+) {
+    if writer.config().verbosity >= level {
+        sub_bullet(writer, s);
+    }
 }
 
 #[cfg(feature = "fun_run")]
@@ -98,160 +80,668 @@ pub(crate) fn sub_stream_cmd<W: TrailingParagraphSend>(
     sub_stream_with(
         writer,
         crate::style::running_command(command.name()),
+        None,
         |stdout, stderr| command.stream_output(stdout, stderr),
+        Result::is_err,
     )
 }
 
 #[cfg(feature = "fun_run")]
-pub fn sub_time_cmd<W>(
-    writer: ParagraphInspectWrite<W>,
+pub(crate) fn sub_time_cmd<W: TrailingParagraphSend>(
+    writer: &mut W,
     mut command: impl fun_run::CommandWithName,
-) -> Result<fun_run::NamedOutput, fun_run::CmdError>
+) -> Result<fun_run::NamedOutput, fun_run::CmdError> {
+    let name = crate::style::running_command(command.name());
+    let (output, _) = time_with(writer, name, || command.named_output());
+    output
+}
+
+/// Run `f` while a timer prints dots, then render how long it took
+///
+/// The dots are printed from a background thread and sent back to be written by `writer`.
+#[cfg(feature = "fun_run")]
+fn time_with<W, T>(writer: &mut W, s: impl AsRef<str>, mut f: impl FnMut() -> T) -> (T, Duration)
 where
-    W: Write + Send + Sync + 'static,
+    W: TrailingParagraphSend,
+    T: 'static,
 {
-    let timer = sub_start_timer(
+    let options = TimerOptions::default();
+    let (ticks, _, wrap) = timer_start_with(writer, s, &options);
+    let start = Instant::now();
+    let out = &mut *writer;
+    let output = mpsc_stream_to_output(
+        |sender| {
+            let background = sub_start_print_interval(
+                sender,
+                options.interval,
+                ticks.clone(),
+                Tick::Append(ticks.tick.clone()),
+                wrap.clone(),
+            );
+            let output = f();
+            background.stop().expect("constructed with valid state");
+            output
+        },
+        |recv| {
+            for message in recv {
+                out.write_all(&message).expect("Writer to not be closed");
+            }
+        },
+    );
+    let elapsed = start.elapsed();
+    render(writer, Event::TimerDone(elapsed));
+    (output, elapsed)
+}
+
+/// Time a command with dots like [`sub_time_cmd`] while capturing its output
+///
+/// The output is only written when the command fails, as a stream under the timer followed by
+/// the error. Lines of stdout and stderr are kept in the order they were written.
+#[cfg(feature = "fun_run")]
+pub(crate) fn sub_time_cmd_stream_on_failure<W: TrailingParagraphSend>(
+    writer: &mut W,
+    mut command: impl fun_run::CommandWithName,
+) -> Result<fun_run::NamedOutput, fun_run::CmdError> {
+    let name = command.name();
+    let config = writer.config();
+    let renderer = config.renderer;
+    let redraws = config.redraws;
+    let mut captured = Vec::new();
+    let (output, elapsed) = time_with(writer, crate::style::running_command(&name), || {
+        mpsc_stream_to_output(
+            |capture| {
+                command.stream_output(
+                    RedrawWrite::new(format_stream_writer(capture.clone()), redraws),
+                    RedrawWrite::new(format_stream_writer(capture.clone()), redraws),
+                )
+            },
+            |recv| captured.extend(recv),
+        )
+    });
+
+    if let Err(error) = &output {
+        if captured.iter().any(|line| !line.is_empty()) {
+            render(
+                writer,
+                Event::StreamStart(format!("Output of {}", crate::style::command(&name))),
+            );
+            for line in captured {
+                stream_line(writer, renderer.as_ref(), None, line);
+            }
+            render(writer, Event::StreamDone(elapsed));
+        }
+        self::error(writer, error.to_string());
+    }
+    output
+}
+
+/// Stream a command keeping only the last `lines` lines of its output, see [`Tail`]
+///
+/// At [`Verbosity::Quiet`] nothing is written unless the command fails.
+#[cfg(feature = "fun_run")]
+pub(crate) fn sub_stream_cmd_tail<W: TrailingParagraphSend>(
+    writer: &mut W,
+    mut command: impl fun_run::CommandWithName,
+    lines: usize,
+) -> Result<fun_run::NamedOutput, fun_run::CmdError> {
+    render(
         writer,
-        Instant::now(),
-        style::running_command(command.name()),
+        Event::StreamStart(crate::style::running_command(command.name())),
     );
-    let output = command.named_output();
-    timer.done();
+
+    let config = writer.config();
+    let redraws = config.redraws;
+    let quiet = config.verbosity == Verbosity::Quiet;
+    let duration = Instant::now();
+    let mut tail = Tail::new(writer, lines);
+    let out = &mut *writer;
+    let output = mpsc_stream_to_output(
+        |sender| {
+            command.stream_output(
+                RedrawWrite::new(format_stream_writer(sender.clone()), redraws),
+                RedrawWrite::new(format_stream_writer(sender.clone()), redraws),
+            )
+        },
+        |recv| {
+            for line in recv {
+                tail.push(out, line);
+            }
+        },
+    );
+
+    tail.finish(writer, !quiet || output.is_err());
+    render(writer, Event::StreamDone(duration.elapsed()));
     output
 }
 
-pub(crate) fn sub_stream_with<W, T, F>(writer: &mut W, s: impl AsRef<str>, mut f: F) -> T
+/// Stream two writers, when `timestamps` are given each line is prefixed with when it arrived
+///
+/// At [`Verbosity::Quiet`] lines are held back and only written when the output has `failed`.
+pub(crate) fn sub_stream_with<W, T, F>(
+    writer: &mut W,
+    s: impl AsRef<str>,
+    timestamps: Option<Timestamps>,
+    mut f: F,
+    failed: impl Fn(&T) -> bool,
+) -> T
 where
     W: TrailingParagraphSend,
     F: FnMut(Box<dyn Write + Send + Sync>, Box<dyn Write + Send + Sync>) -> T,
     T: 'static,
 {
-    sub_bullet(writer, s);
-    writeln!(writer).expect("writer open");
+    render(writer, Event::StreamStart(s.as_ref().to_string()));
 
+    let config = writer.config();
+    let renderer = config.renderer;
+    let redraws = config.redraws;
     let duration = Instant::now();
-    mpsc_stream_to_output(
+    let stamp =
+        timestamps.map(|timestamps| Stamp::new(timestamps, duration, config.theme.dim.clone()));
+    let quiet = config.verbosity == Verbosity::Quiet;
+    let mut held = Vec::new();
+    let out = &mut *writer;
+    let output = mpsc_stream_to_output(
         |sender| {
             f(
                 // The Senders are boxed to hide the types from the caller so it can be changed
                 // in the future. They only need to know they have a `Write + Send + Sync` type.
-                Box::new(format_stream_writer(sender.clone())),
-                Box::new(format_stream_writer(sender.clone())),
+                Box::new(RedrawWrite::new(
+                    format_stream_writer(sender.clone()),
+                    redraws,
+                )),
+                Box::new(RedrawWrite::new(
+                    format_stream_writer(sender.clone()),
+                    redraws,
+                )),
             )
         },
-        move |recv| {
+        |recv| {
             // When it receives input, it writes it to the current `Write` value.
             //
             // When the senders close their channel this loop will exit
-            for message in recv {
-                writer.write_all(&message).expect("Writer to not be closed");
+            for line in recv {
+                if quiet {
+                    held.push(line);
+                } else {
+                    stream_line(out, renderer.as_ref(), stamp.as_ref(), line);
+                }
             }
+        },
+    );
 
-            if !writer.trailing_paragraph() {
-                writeln!(writer).expect("Writer to not be closed");
-            }
+    if failed(&output) {
+        for line in held {
+            stream_line(writer, renderer.as_ref(), stamp.as_ref(), line);
+        }
+    }
+    render(writer, Event::StreamDone(duration.elapsed()));
+    output
+}
 
-            sub_bullet(
-                writer,
-                format!(
-                    "Done {}",
-                    style::details(duration_format::human(&duration.elapsed()))
-                ),
-            )
+#[cfg(feature = "fun_run")]
+pub(crate) fn sub_stream_cmds<W, C>(
+    writer: &mut W,
+    s: impl AsRef<str>,
+    commands: Vec<Labelled<C>>,
+) -> Vec<Result<fun_run::NamedOutput, fun_run::CmdError>>
+where
+    W: TrailingParagraphSend,
+    C: fun_run::CommandWithName + Send,
+{
+    let streams = commands
+        .into_iter()
+        .map(|command| Labelled {
+            label: command.label,
+            color: command.color,
+            inner: {
+                let mut command = command.inner;
+                move |stdout, stderr| command.stream_output(stdout, stderr)
+            },
+        })
+        .collect();
+    sub_stream_many_with(
+        writer,
+        s,
+        streams,
+        |result| {
+            Some(match result {
+                Ok(_) => String::from("exit status 0"),
+                Err(fun_run::CmdError::SystemError(_, _)) => String::from("failed to start"),
+                Err(error) => match error.status().code() {
+                    Some(code) => format!("exit status {code}"),
+                    None => error.status().to_string(),
+                },
+            })
         },
+        Result::is_err,
     )
 }
 
+/// Stream several sources at once, each line is prefixed with the label of its source
+///
+/// Lines are written whole so output of one source never splits a line of another. The done
+/// line reports the duration of each source along with the `details` of its result, if any.
+///
+/// At [`Verbosity::Quiet`] lines are held back and only written when any output has `failed`.
+pub(crate) fn sub_stream_many_with<W, T, F>(
+    writer: &mut W,
+    s: impl AsRef<str>,
+    streams: Vec<Labelled<F>>,
+    details: impl Fn(&T) -> Option<String>,
+    failed: impl Fn(&T) -> bool,
+) -> Vec<T>
+where
+    W: TrailingParagraphSend,
+    F: FnOnce(Box<dyn Write + Send + Sync>, Box<dyn Write + Send + Sync>) -> T + Send,
+    T: Send + 'static,
+{
+    render(writer, Event::StreamStart(s.as_ref().to_string()));
+
+    let config = writer.config();
+    let renderer = config.renderer;
+    let line_safe = config.line_safe;
+    let redraws = config.redraws;
+    let started = Instant::now();
+    let width = streams
+        .iter()
+        .map(|stream| visible_width(&stream.label))
+        .max()
+        .unwrap_or_default();
+    let quiet = config.verbosity == Verbosity::Quiet;
+    let mut held = Vec::new();
+    let mut streams = Some(streams);
+    let out = &mut *writer;
+    let finished = mpsc_stream_to_output(
+        |sender| {
+            let streams = streams.take().expect("Streams are only started once");
+            thread::scope(|scope| {
+                let handles = streams
+                    .into_iter()
+                    .map(|stream| {
+                        let stdout = RedrawWrite::new(
+                            prefixed_stream_writer(sender.clone(), stream.prefix(width), line_safe),
+                            redraws,
+                        );
+                        let stderr = RedrawWrite::new(
+                            prefixed_stream_writer(sender.clone(), stream.prefix(width), line_safe),
+                            redraws,
+                        );
+                        let (label, f) = (stream.label, stream.inner);
+                        scope.spawn(move || {
+                            let started = Instant::now();
+                            let output = f(Box::new(stdout), Box::new(stderr));
+                            (label, output, started.elapsed())
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                handles
+                    .into_iter()
+                    .map(|handle| {
+                        handle
+                            .join()
+                            .unwrap_or_else(|error| std::panic::resume_unwind(error))
+                    })
+                    .collect::<Vec<_>>()
+            })
+        },
+        |recv| {
+            for line in recv {
+                if quiet {
+                    held.push(line);
+                } else {
+                    stream_line(out, renderer.as_ref(), None, line);
+                }
+            }
+        },
+    );
+
+    if finished.iter().any(|(_, output, _)| failed(output)) {
+        for line in held {
+            stream_line(writer, renderer.as_ref(), None, line);
+        }
+    }
+
+    let summary = finished
+        .iter()
+        .map(|(label, output, duration)| {
+            let duration = duration_format::human(duration);
+            match details(output) {
+                Some(details) => format!("{label} {details} in {duration}"),
+                None => format!("{label} in {duration}"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    render(
+        writer,
+        Event::StreamsDone {
+            duration: started.elapsed(),
+            summary,
+        },
+    );
+
+    finished.into_iter().map(|(_, output, _)| output).collect()
+}
+
+/// Buffers output into whole lines that start with `prefix`, when `line_safe` each redraw of a
+/// line gets a prefix of its own
+fn prefixed_stream_writer<S>(stream_to: S, prefix: String, line_safe: bool) -> MappedWrite<S>
+where
+    S: Write + Send + Sync,
+{
+    line_mapped(stream_to, move |line| {
+        if line.is_empty() {
+            line
+        } else if line_safe {
+            split_redraws(&line)
+                .into_iter()
+                .flat_map(|line| [prefix.as_bytes(), &line].concat())
+                .collect()
+        } else {
+            [prefix.as_bytes(), &line].concat()
+        }
+    })
+}
+
+/// Emit and write a single line of a stream, prefixed with a timestamp when given a `stamp`
+///
+/// When the writer is line safe, each redraw of the line is written as a line of its own.
+pub(crate) fn stream_line<W: TrailingParagraph>(
+    writer: &mut W,
+    renderer: &dyn Renderer,
+    stamp: Option<&Stamp>,
+    line: Vec<u8>,
+) {
+    if line.is_empty() {
+        return;
+    }
+    let config = writer.config();
+    let line = config.redactions.line(line);
+    let lines = if config.line_safe {
+        split_redraws(&line)
+    } else {
+        vec![line]
+    };
+    for line in lines {
+        config.events.emit(Event::StreamOutput(
+            String::from_utf8_lossy(&line).into_owned(),
+        ));
+        let line = renderer.stream_line(line);
+        let line = match stamp {
+            Some(stamp) => stamp.line(line),
+            None => line,
+        };
+        writer.write_all(&line).expect("Writer to not be closed");
+    }
+}
+
+/// Writer for [`state::Stream`], collapses redraws, then emits each line and renders it,
+/// prefixed with a timestamp when given a `stamp`
+pub(crate) fn stream_state_writer<W: Write>(
+    write: ParagraphInspectWrite<W>,
+    stamp: Option<Stamp>,
+) -> RedrawWrite<MappedWrite<ParagraphInspectWrite<W>>> {
+    let Config {
+        redraws,
+        renderer,
+        events,
+        redactions,
+        line_safe,
+        verbosity,
+        ..
+    } = write.config.clone();
+    let quiet = verbosity == Verbosity::Quiet;
+    let mapped = line_mapped(write, move |line| {
+        if quiet {
+            return Vec::new();
+        }
+        let line = redactions.line(line);
+        // The last line can be empty, it is still rendered
+        let lines = if line_safe && !line.is_empty() {
+            split_redraws(&line)
+        } else {
+            vec![line]
+        };
+        lines
+            .into_iter()
+            .flat_map(|line| {
+                if !line.is_empty() {
+                    events.emit(Event::StreamOutput(
+                        String::from_utf8_lossy(&line).into_owned(),
+                    ));
+                }
+                let line = renderer.stream_line(line);
+                match &stamp {
+                    Some(stamp) => stamp.line(line),
+                    None => line,
+                }
+            })
+            .collect()
+    });
+    RedrawWrite::new(mapped, redraws)
+}
+
+/// Stream the stdout and stderr of a command line by line as it runs, capturing both
+#[cfg(feature = "tokio")]
+pub(crate) async fn sub_stream_cmd_async<W: TrailingParagraph>(
+    writer: &mut W,
+    command: &mut tokio::process::Command,
+) -> io::Result<std::process::Output> {
+    use crate::redraw::Collapse;
+    use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+
+    /// Send each line including the newline, the receiver ends once both senders are done
+    async fn read_lines(
+        mut reader: impl AsyncBufRead + Unpin,
+        sender: tokio::sync::mpsc::UnboundedSender<(bool, Vec<u8>)>,
+        is_stdout: bool,
+    ) -> io::Result<()> {
+        loop {
+            let mut line = Vec::new();
+            if reader.read_until(b'\n', &mut line).await? == 0 {
+                return Ok(());
+            }
+            // Ignore if the receiver is gone, the output is no longer needed
+            let _ = sender.send((is_stdout, line));
+        }
+    }
+
+    // Named the same as a `fun_run` command such as from `sub_stream_cmd`
+    let name = fun_run::display(command.as_std_mut());
+    // Spawn before announcing so a command that can't start doesn't leave a dangling header
+    let mut child = command
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    render(
+        writer,
+        Event::StreamStart(crate::style::running_command(name)),
+    );
+
+    let config = writer.config();
+    let renderer = config.renderer;
+    let duration = Instant::now();
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let stdout = child.stdout.take().map(BufReader::new);
+    let stderr = child.stderr.take().map(BufReader::new);
+    let readers = tokio::spawn({
+        let sender = sender.clone();
+        async move {
+            let stdout = async {
+                match stdout {
+                    Some(stdout) => read_lines(stdout, sender.clone(), true).await,
+                    None => Ok(()),
+                }
+            };
+            let stderr = async {
+                match stderr {
+                    Some(stderr) => read_lines(stderr, sender.clone(), false).await,
+                    None => Ok(()),
+                }
+            };
+            tokio::try_join!(stdout, stderr).map(|_| ())
+        }
+    });
+    drop(sender);
+
+    let mut output = std::process::Output {
+        status: std::process::ExitStatus::default(),
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+    };
+    // Lines are read whole, redraws within a line arrive at once
+    let mut collapse = config.redraws.map(Collapse::new);
+    let quiet = config.verbosity == Verbosity::Quiet;
+    let mut held = Vec::new();
+    while let Some((is_stdout, line)) = receiver.recv().await {
+        if is_stdout {
+            output.stdout.extend_from_slice(&line);
+        } else {
+            output.stderr.extend_from_slice(&line);
+        }
+        let line = match collapse.as_mut() {
+            Some(collapse) => collapse.push(&line, Instant::now()),
+            None => line,
+        };
+        for line in line.split_inclusive(|&byte| byte == b'\n') {
+            if quiet {
+                held.push(line.to_vec());
+            } else {
+                stream_line(writer, renderer.as_ref(), None, line.to_vec());
+            }
+        }
+    }
+    let rest = collapse.as_mut().map(Collapse::finish).unwrap_or_default();
+    if quiet {
+        held.push(rest);
+    } else {
+        stream_line(writer, renderer.as_ref(), None, rest);
+    }
+    let status = match readers.await.map_err(io::Error::other) {
+        Ok(Ok(())) => child.wait().await,
+        Ok(Err(error)) | Err(error) => Err(error),
+    };
+    if !status.as_ref().is_ok_and(std::process::ExitStatus::success) {
+        for line in held {
+            stream_line(writer, renderer.as_ref(), None, line);
+        }
+    }
+
+    render(writer, Event::StreamDone(duration.elapsed()));
+    output.status = status?;
+    Ok(output)
+}
+
+/// Same as [`sub_start_timer`] with ticks driven by a `tokio` task
+#[cfg(feature = "tokio")]
+pub(crate) fn sub_start_timer_async<W>(
+    mut writer: ParagraphInspectWrite<W>,
+    started: Option<Instant>,
+    s: impl AsRef<str>,
+    options: &TimerOptions,
+) -> Print<state::AsyncBackground<W>>
+where
+    W: Write + Send + Sync + 'static,
+{
+    let (ticks, tick, wrap) = timer_start_with(&mut writer, s, options);
+    let guard = background_printer::print_interval_async(
+        writer,
+        options.interval,
+        ticks.start,
+        tick,
+        ticks.end,
+        ticks.on_drop,
+        wrap,
+    );
+
+    Print {
+        started,
+        state: state::AsyncBackground {
+            started: Instant::now(),
+            write: guard,
+        },
+    }
+}
+
 pub(crate) fn sub_start_timer<W>(
-    writer: ParagraphInspectWrite<W>,
+    mut writer: ParagraphInspectWrite<W>,
     started: Instant,
     s: impl AsRef<str>,
+    options: &TimerOptions,
 ) -> Print<state::Background<W>>
 where
     W: Write + Send + Sync + 'static,
 {
-    let guard = sub_start_print_interval(writer, s);
+    let (ticks, tick, wrap) = timer_start_with(&mut writer, s, options);
+    let guard = sub_start_print_interval(writer, options.interval, ticks, tick, wrap);
 
     Print {
         started: Some(started),
         state: state::Background {
             started: Instant::now(),
             write: guard,
+            depth: Event::SUB_BULLET_DEPTH,
+            parent: std::marker::PhantomData,
         },
     }
 }
 
-pub(crate) fn sub_start_print_interval<W: Write + Send + Sync + 'static>(
-    mut writer: W,
+/// Render the start of a timer and return the ticks to print in the background
+pub(crate) fn timer_start<W: TrailingParagraph>(writer: &mut W, s: impl AsRef<str>) -> TimerTicks {
+    render(writer, Event::TimerStart(s.as_ref().to_string()));
+    let config = writer.config();
+    config.renderer.timer_ticks(&config.theme)
+}
+
+/// Render the start of a timer and return the ticks adjusted by the options
+pub(crate) fn timer_start_with<W: TrailingParagraph>(
+    writer: &mut W,
     s: impl AsRef<str>,
-) -> PrintGuard<W> {
-    // Do not emit a newline after the message
-    write!(&mut writer, "{}", sub_bullet_prefix(s)).expect("writer not to be closed");
-    writer.flush().expect("Output error: UI writer closed");
+    options: &TimerOptions,
+) -> (TimerTicks, Tick, Option<Wrap>) {
+    let ticks = options.apply(timer_start(writer, s));
+    let config = writer.config();
+    let tick = options.tick(&ticks, config.can_redraw(), &config.theme);
+    let wrap = options.wrap(writer.column(), config.width);
+    (ticks, tick, wrap)
+}
 
+pub(crate) fn sub_start_print_interval<W: Write + Send + Sync + 'static>(
+    writer: W,
+    interval: Duration,
+    ticks: TimerTicks,
+    tick: Tick,
+    wrap: Option<Wrap>,
+) -> PrintGuard<W> {
     background_printer::print_interval(
         writer,
-        std::time::Duration::from_secs(1),
-        ansi_escape::wrap_ansi_escape_each_line(&ANSI::Dim, " ."),
-        ansi_escape::wrap_ansi_escape_each_line(&ANSI::Dim, "."),
-        ansi_escape::wrap_ansi_escape_each_line(&ANSI::Dim, ". "),
-        "(Error)".to_string(),
+        interval,
+        ticks.start,
+        tick,
+        ticks.end,
+        ticks.on_drop,
+        wrap,
     )
 }
 
-pub(crate) fn all_done<W: Write>(writer: &mut W, started: &Option<Instant>) {
-    if let Some(started) = started {
-        bullet(
-            writer,
-            format!(
-                "Done (finished in {})",
-                duration_format::human(&started.elapsed())
-            ),
-        );
-    } else {
-        bullet(writer, "Done");
-    }
-}
-
-pub(crate) fn write_paragraph<W: TrailingParagraph>(io: &mut W, color: &ANSI, s: impl AsRef<str>) {
-    let contents = s.as_ref().trim();
-
-    if !io.trailing_paragraph() {
-        writeln!(io).expect("writer open");
-    }
-
-    writeln!(
-        io,
-        "{}",
-        ansi_escape::wrap_ansi_escape_each_line(
-            color,
-            prefix_lines(contents, |_, line| {
-                // Avoid adding trailing whitespace to the line, if there was none already.
-                // The `\n` case is required since `prefix_lines` uses `str::split_inclusive`,
-                // which preserves any trailing newline characters if present.
-                if line.is_empty() || line == "\n" {
-                    String::from("!")
-                } else {
-                    String::from("! ")
-                }
-            }),
-        ),
-    )
-    .expect("writer open");
-    writeln!(io).expect("writer open");
-    io.flush().expect("writer open");
+pub(crate) fn all_done<W: TrailingParagraph>(writer: &mut W, started: &Option<Instant>) {
+    render(
+        writer,
+        Event::AllDone(started.map(|started| started.elapsed())),
+    );
 }
 
 pub(crate) fn warning<W: TrailingParagraph>(writer: &mut W, s: impl AsRef<str>) {
-    write_paragraph(writer, &ANSI::Yellow, s);
+    render(writer, Event::Warning(s.as_ref().to_string()));
 }
 
 pub(crate) fn error<W: TrailingParagraph>(writer: &mut W, s: impl AsRef<str>) {
-    write_paragraph(writer, &ANSI::Red, s);
+    render(writer, Event::Error(s.as_ref().to_string()));
 }
 
 pub(crate) fn important<W: TrailingParagraph>(writer: &mut W, s: impl AsRef<str>) {
-    write_paragraph(writer, &ANSI::BoldCyan, s);
+    render(writer, Event::Important(s.as_ref().to_string()));
 }
 
 /// Constructs a writer that buffers written data until given marker byte is encountered and
@@ -455,12 +945,51 @@ mod test {
         )
     }
 
+    #[test]
+    fn test_time_cmd_stream_on_failure() {
+        let writer = LockedWriter::new(Vec::new());
+        let reader = writer.clone();
+        let mut writer = ParagraphInspectWrite::new(writer);
+        self::sub_time_cmd_stream_on_failure(
+            &mut writer,
+            Command::new("bash").args(["-c", "echo hidden"]),
+        )
+        .unwrap();
+        self::sub_time_cmd_stream_on_failure(
+            &mut writer,
+            Command::new("bash").args(["-c", "echo out; echo err >&2; exit 2"]),
+        )
+        .unwrap_err();
+        drop(writer);
+
+        let expected = formatdoc! {"
+            - Running `bash -c \"echo hidden\"` ... (< 0.1s)
+            - Running `bash -c \"echo out; echo err >&2; exit 2\"` ... (< 0.1s)
+            - Output of `bash -c \"echo out; echo err >&2; exit 2\"`
+
+                out
+                err
+
+            - Done (< 0.1s)
+
+          ! Command failed `bash -c \"echo out; echo err >&2; exit 2\"`
+          ! exit status: 2
+          ! stdout: <see above>
+          ! stderr: <see above>
+
+        "};
+        assert_eq!(
+            expected,
+            strip_ansi(String::from_utf8_lossy(&reader.unwrap()))
+        );
+    }
+
     #[test]
     fn test_time_cmd() {
         let writer = LockedWriter::new(Vec::new());
         let reader = writer.clone();
         self::sub_time_cmd(
-            ParagraphInspectWrite::new(writer),
+            &mut ParagraphInspectWrite::new(writer),
             Command::new("bash").arg("-c").arg("echo hello"),
         )
         .unwrap();